@@ -0,0 +1,65 @@
+//! 임의 바이트를 Decoder 와 PACKET::parse 에 넣는다.
+//! 첫 바이트로 형식 (LEN 폭, CS, framing, resync) 을 고르고 나머지를 수신 스트림으로 쓴다.
+#![no_main]
+#![allow(non_snake_case)]
+
+use libfuzzer_sys::fuzz_target;
+use RUST_tutorial::protocol::{ChecksumKind, Decoder, FrameSpec, Framing, ParseEvent, PACKET};
+
+fn spec(selector: u8) -> FrameSpec {
+    let mut spec = if selector & 0x01 == 0 {
+        FrameSpec::default()
+    } else {
+        FrameSpec::extended()
+    };
+    spec.length_includes_overhead = selector & 0x02 == 0;
+    spec.checksum = match (selector >> 2) % 5 {
+        0 => ChecksumKind::XorIncrement,
+        1 => ChecksumKind::Sum8,
+        2 => ChecksumKind::Xor8,
+        3 => ChecksumKind::Crc8,
+        _ => ChecksumKind::Crc16Modbus,
+    };
+    spec.framing = match selector >> 5 & 0x03 {
+        0 => Framing::None,
+        1 => Framing::Dle,
+        2 => Framing::Slip,
+        _ => Framing::Cobs,
+    };
+    spec
+}
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, stream)) = data.split_first() else {
+        return;
+    };
+    let spec = spec(selector);
+
+    let mut decoder = Decoder::new();
+    decoder.set_spec(&spec);
+    decoder.set_resync(selector & 0x80 != 0);
+    // read 경계가 프레임 중간에 오도록 잘라서 넣는다
+    for chunk in stream.chunks(7) {
+        for packet in decoder.feed(chunk).flatten() {
+            // 디코딩된 프레임은 다시 직렬화해도 같은 프레임이어야 한다
+            let mut reparsed = PACKET::with_spec(spec.clone());
+            let frame = packet
+                .serialize()
+                .into_iter()
+                .find_map(|byte| match reparsed.parse(byte) {
+                    ParseEvent::Frame(frame) => Some(frame),
+                    _ => None,
+                })
+                .expect("re-serialized frame does not parse");
+            assert_eq!(frame.serialize(), packet.serialize());
+            let _ = packet.to_string();
+        }
+    }
+
+    let mut packet = PACKET::with_spec(spec);
+    for &byte in stream {
+        if let ParseEvent::Frame(frame) = packet.parse(byte) {
+            let _ = frame.to_string();
+        }
+    }
+});
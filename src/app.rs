@@ -1,13 +1,26 @@
-use crate::protocol::PACKET;
-use crate::serial::BaudRate;
-use crate::serial::ComPort;
+use crate::filter::PacketFilter;
+use crate::library::{Command, Library};
+use crate::packet_log::{ChecksumStatus, Direction, LogEntry, PacketLog};
+use crate::protocol::{
+    parse_hex, Checksum, ChecksumKind, DecoderConfig, Field, FrameSpec, Framing, PacketBuilder,
+    PACKET,
+};
+use crate::rfc2217::Rfc2217Transport;
+use crate::scheduler::Schedule;
+use crate::serial::{BaudRate, DataBits, FlowControl, Parity, SerialConfig, StopBits};
+use crate::serial::{PortEntry, SERIAL};
+use crate::session::{Session, SessionEvent};
+use crate::transport::{Loopback, ReplayTransport, TcpTransport, Transport, TransportKind};
 use eframe::Frame;
 use egui::emath::align;
 use egui::frame;
 use egui::vec2;
 use egui::Widget;
-use egui::{Align, Button, Color32, InnerResponse, Layout};
-use lipsum::lipsum;
+use egui::{Align, Button, Color32, Layout, RichText};
+use log::{debug, warn};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use strum::IntoEnumIterator;
 
 pub const WIDNOW_X_MIN: f32 = 800.0;
@@ -17,30 +30,129 @@ pub const WIDNOW_Y_MIN: f32 = 600.0;
 #[serde(default)] // if we add new fields, give them default values when deserializing old state
 pub struct SerialApp {
     // Example stuff:
-    baud_rate: BaudRate,
-    com_port: ComPort,
+    serial_config: SerialConfig,
+    // 선택한 포트. USB 포트는 시리얼 번호로도 저장해서 경로가 바뀌어도 다시 찾는다
+    port_name: String,
+    port_serial_number: Option<String>,
+    transport_kind: TransportKind,
+    tcp_address: String,
+    replay_path: String,
+    // 열린 연결을 공유하는 TCP server 주소
+    share_address: String,
+    // 포트가 끊기면 같은 장치가 다시 나타날 때 자동으로 연다
+    auto_reconnect: bool,
+    // 바이트 간격이 이 값(ms)을 넘으면 조립 중인 프레임을 버린다. 0 이면 사용 안함
+    frame_timeout_ms: u32,
+    frame_spec: FrameSpec,
+    frame_spec_path: String,
 
+    // hex 목록 (`C1, 10-1F, !05`) 과 필터 식. 로그와 저장에 적용한다
     id_filter: String,
     cmd_filter: String,
+    expr_filter: String,
+    log_export_path: String,
 
+    // 전송할 패킷의 hex 입력. LEN, CS 는 수동 입력을 켰을 때만 쓴다
+    send_id: String,
+    send_cmd: String,
+    send_seq: String,
+    send_data: String,
+    send_len: String,
+    send_cs: String,
+    send_len_manual: bool,
+    send_cs_manual: bool,
+    // 반복 전송 간격 (ms) 과 횟수. 횟수 0 은 멈출 때까지
     send_delay: u32,
     send_count: u32,
+    send_auto_seq: bool,
 
+    // 새 행이 들어오면 로그 맨 아래로 따라간다
+    log_auto_scroll: bool,
+
+    // 자주 보내는 명령 목록과 가져오기/내보내기 파일
+    library: Library,
+    library_path: String,
+
+    #[serde(skip)]
+    ports: Vec<PortEntry>,
+    #[serde(skip)]
+    decoder_config: Arc<Mutex<DecoderConfig>>,
+    #[serde(skip)]
+    frame_spec_error: Option<String>,
+    #[serde(skip)]
+    session: Option<Session>,
+    #[serde(skip)]
+    connecting: Option<mpsc::Receiver<Result<Box<dyn Transport>, String>>>,
+    #[serde(skip)]
+    connect_error: Option<String>,
     #[serde(skip)]
-    packet: PACKET,
+    share_error: Option<String>,
+    #[serde(skip)]
+    packet_log: PacketLog,
+    #[serde(skip)]
+    filter_error: Option<String>,
+    #[serde(skip)]
+    send_error: Option<String>,
+    #[serde(skip)]
+    export_open: bool,
+    #[serde(skip)]
+    export_status: Option<Result<String, String>>,
+    #[serde(skip)]
+    library_editing: Option<usize>,
+    #[serde(skip)]
+    library_status: Option<Result<String, String>>,
 }
 
 impl Default for SerialApp {
     fn default() -> Self {
         Self {
             // Example stuff:
-            baud_rate: BaudRate::B9600,
-            com_port: ComPort::COM1,
+            serial_config: SerialConfig::default(),
+            port_name: String::new(),
+            port_serial_number: None,
+            transport_kind: TransportKind::Serial,
+            tcp_address: String::new(),
+            replay_path: String::new(),
+            share_address: String::from("0.0.0.0:5000"),
+            auto_reconnect: true,
+            frame_timeout_ms: 0,
+            frame_spec: FrameSpec::default(),
+            frame_spec_path: String::new(),
             id_filter: String::new(),
             cmd_filter: String::new(),
+            expr_filter: String::new(),
+            log_export_path: String::from("packet_log.csv"),
+            send_id: String::from("00"),
+            send_cmd: String::from("00"),
+            send_seq: String::from("00"),
+            send_data: String::new(),
+            send_len: String::new(),
+            send_cs: String::new(),
+            send_len_manual: false,
+            send_cs_manual: false,
             send_delay: 100,
             send_count: 1,
-            packet: PACKET::new(),
+            send_auto_seq: false,
+            log_auto_scroll: true,
+            library: Library::default(),
+            library_path: String::from("commands.json"),
+            ports: Vec::new(),
+            decoder_config: Arc::new(Mutex::new(DecoderConfig {
+                resync: true,
+                ..Default::default()
+            })),
+            frame_spec_error: None,
+            session: None,
+            connecting: None,
+            connect_error: None,
+            share_error: None,
+            packet_log: PacketLog::new(),
+            filter_error: None,
+            send_error: None,
+            export_open: false,
+            export_status: None,
+            library_editing: None,
+            library_status: None,
         }
     }
 }
@@ -53,47 +165,568 @@ impl SerialApp {
 
         // Load previous app state (if any).
         // Note that you must enable the `persistence` feature for this to work.
-        if let Some(storage) = cc.storage {
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut app: SerialApp = match cc.storage {
+            Some(storage) => eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default(),
+            None => Default::default(),
+        };
+
+        // 저장된 frame spec 이 예전 형식이거나 손상됐으면 기본값으로 시작한다
+        if let Err(e) = app.frame_spec.validate() {
+            warn!("Saved frame spec ignored : {}", e);
+            app.frame_spec = FrameSpec::default();
+        }
+        app.apply_decoder_config();
+        app.apply_filter();
+        app.refresh_ports();
+
+        app
+    }
+
+    // 포트 목록을 다시 읽고, 저장된 USB 시리얼 번호와 같은 포트가 있으면 그 경로로 바꾼다
+    fn refresh_ports(&mut self) {
+        self.ports = SERIAL::scan_ports();
+
+        if let Some(serial_number) = &self.port_serial_number {
+            if let Some(port) = self
+                .ports
+                .iter()
+                .find(|port| port.serial_number.as_ref() == Some(serial_number))
+            {
+                self.port_name = port.port_name.clone();
+            }
+        }
+    }
+
+    fn select_port(&mut self, port: &PortEntry) {
+        self.port_name = port.port_name.clone();
+        self.port_serial_number = port.serial_number.clone();
+    }
+
+    // 연결을 여는 작업. TCP 접속은 오래 걸릴 수 있으므로 UI 쓰레드 밖에서 실행한다
+    fn transport_opener(
+        &self,
+    ) -> impl FnOnce() -> Result<Box<dyn Transport>, String> + Send + 'static {
+        let kind = self.transport_kind;
+        // 목록에 없는 포트(가상 포트 등)도 경로만으로 열 수 있게 한다
+        let port = self
+            .ports
+            .iter()
+            .find(|port| port.port_name == self.port_name)
+            .cloned()
+            .unwrap_or_else(|| PortEntry {
+                port_name: self.port_name.clone(),
+                ..Default::default()
+            });
+        let serial_config = self.serial_config;
+        let tcp_address = self.tcp_address.clone();
+        let replay_path = self.replay_path.clone();
+
+        move || -> Result<Box<dyn Transport>, String> {
+            match kind {
+                TransportKind::Serial => {
+                    if port.port_name.is_empty() {
+                        return Err(String::from("No port selected"));
+                    }
+
+                    let mut serial = SERIAL::new();
+                    serial
+                        .open_device(&port, &serial_config)
+                        .map_err(|e| format!("{} : {}", port.port_name, e))?;
+                    Ok(Box::new(serial))
+                }
+                TransportKind::Tcp => match TcpTransport::connect(&tcp_address) {
+                    Ok(tcp) => Ok(Box::new(tcp)),
+                    Err(e) => Err(format!("{} : {}", tcp_address, e)),
+                },
+                TransportKind::Rfc2217 => {
+                    match Rfc2217Transport::connect(&tcp_address, &serial_config) {
+                        Ok(rfc2217) => Ok(Box::new(rfc2217)),
+                        Err(e) => Err(format!("{} : {}", tcp_address, e)),
+                    }
+                }
+                TransportKind::Replay => match ReplayTransport::open(&replay_path) {
+                    Ok(replay) => Ok(Box::new(replay)),
+                    Err(e) => Err(format!("{} : {}", replay_path, e)),
+                },
+                TransportKind::Loopback => Ok(Box::new(Loopback::new())),
+            }
+        }
+    }
+
+    fn connect(&mut self) {
+        let open = self.transport_opener();
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let _ = tx.send(open());
+        });
+        self.connecting = Some(rx);
+        self.connect_error = None;
+    }
+
+    // 연결 쓰레드가 끝났으면 세션을 시작한다. 취소했으면 결과는 버려진다
+    fn poll_connect(&mut self) {
+        let Some(connecting) = &self.connecting else {
+            return;
+        };
+        let result = match connecting.try_recv() {
+            Ok(result) => result,
+            Err(mpsc::TryRecvError::Empty) => return,
+            Err(mpsc::TryRecvError::Disconnected) => Err(String::from("connect failed")),
+        };
+        self.connecting = None;
+
+        match result {
+            Ok(transport) => {
+                self.session = Some(Session::open(
+                    transport,
+                    Arc::clone(&self.decoder_config),
+                    self.auto_reconnect,
+                ));
+            }
+            Err(e) => self.connect_error = Some(e),
+        }
+    }
+
+    fn disconnect(&mut self) {
+        if let Some(session) = self.session.take() {
+            session.close();
+        }
+    }
+
+    // 수신 쓰레드의 알림 처리. 포트가 끊기면 세션을 정리하고 이유를 보여준다
+    fn poll_session(&mut self) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+
+        let name = session.name().to_string();
+        for event in session.poll_events() {
+            match event {
+                SessionEvent::Lost(e) => {
+                    self.connect_error = Some(format!("{} : {}", name, e));
+                    self.packet_log
+                        .push(LogEntry::marker(format!("lost : {} : {}", name, e)));
+                }
+                SessionEvent::Reconnected(name) => {
+                    self.connect_error = None;
+                    self.packet_log
+                        .push(LogEntry::marker(format!("reconnected : {}", name)));
+                    // USB 포트는 경로가 바뀌었을 수 있으므로 목록을 다시 읽어 선택을 맞춘다
+                    if self.transport_kind == TransportKind::Serial {
+                        self.refresh_ports();
+                    }
+                }
+                SessionEvent::Disconnected(e) => {
+                    self.session = None;
+                    self.connect_error = Some(format!("{} : {}", name, e));
+                }
+                SessionEvent::Packet(entry) => self.packet_log.push(entry),
+                SessionEvent::SendFailed(e) => self.send_error = Some(e.to_string()),
+                SessionEvent::SendDone(sent) => self.advance_sequence(sent),
+            }
+        }
+    }
+
+    // 저장된 디코더 설정을 수신 쓰레드에 반영
+    fn apply_decoder_config(&self) {
+        let mut config = self.decoder_config.lock().unwrap();
+        config.spec = self.frame_spec.clone();
+        config.timeout = if self.frame_timeout_ms > 0 {
+            Some(Duration::from_millis(self.frame_timeout_ms as u64))
+        } else {
+            None
+        };
+    }
+
+    // 필터 입력을 다시 읽어 로그에 적용한다. 잘못된 입력이면 이전 필터를 유지한다
+    fn apply_filter(&mut self) {
+        match PacketFilter::parse(&self.id_filter, &self.cmd_filter, &self.expr_filter) {
+            Ok(filter) => {
+                self.packet_log.set_filter(filter);
+                self.filter_error = None;
+            }
+            Err(e) => self.filter_error = Some(e.to_string()),
+        }
+    }
+
+    // 필터를 통과한 로그를 저장한다
+    fn export_log(&mut self) {
+        self.export_status = Some(match self.packet_log.save_csv(&self.log_export_path) {
+            Ok(count) => Ok(format!("{} rows saved to {}", count, self.log_export_path)),
+            Err(e) => Err(format!("{} : {}", self.log_export_path, e)),
+        });
+    }
+
+    fn export_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.export_open;
+        egui::Window::new("Save log as")
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Path :");
+                    ui.text_edit_singleline(&mut self.log_export_path);
+                    if ui.button("Save").clicked() {
+                        self.export_log();
+                    }
+                });
+                if !self.packet_log.filter().is_empty() {
+                    ui.label(format!(
+                        "Filter applied : {} of {} rows",
+                        self.packet_log.visible_len(),
+                        self.packet_log.len()
+                    ));
+                }
+                match &self.export_status {
+                    Some(Ok(message)) => {
+                        ui.colored_label(Color32::GREEN, message);
+                    }
+                    Some(Err(error)) => {
+                        ui.colored_label(Color32::RED, error);
+                    }
+                    None => {}
+                }
+            });
+        self.export_open = open;
+    }
+
+    // 포트 선택, 통신 속도
+    fn serial_port_select(&mut self, ui: &mut egui::Ui) {
+        ui.label("Select COM Port :");
+        let selected_text = match self
+            .ports
+            .iter()
+            .find(|port| port.port_name == self.port_name)
+        {
+            Some(port) => port.to_string(),
+            None if self.port_name.is_empty() => String::from("-"),
+            None => format!("{} (not found)", self.port_name),
+        };
+        let mut selected = None;
+        egui::ComboBox::from_id_salt("Select COM Port : ")
+            .selected_text(selected_text)
+            .width(240.0)
+            .show_ui(ui, |ui| {
+                if self.ports.is_empty() {
+                    ui.label("No ports found");
+                }
+                for port in &self.ports {
+                    let mut response =
+                        ui.selectable_label(port.port_name == self.port_name, port.to_string());
+                    if let (Some(vid), Some(pid)) = (port.vid, port.pid) {
+                        response =
+                            response.on_hover_text(format!("VID {:04X} PID {:04X}", vid, pid));
+                    }
+                    if response.clicked() {
+                        selected = Some(port.clone());
+                    }
+                }
+            });
+        if let Some(port) = selected {
+            self.select_port(&port);
+        }
+        if ui.button("⟳").on_hover_text("Refresh port list").clicked() {
+            self.refresh_ports();
         }
 
-        Default::default()
+        self.baud_rate_select(ui);
+    }
+
+    // 목록의 통신 속도 + 직접 입력
+    fn baud_rate_select(&mut self, ui: &mut egui::Ui) {
+        ui.label("Baud rate :");
+        egui::ComboBox::from_id_salt("Baud rate : ")
+            .selected_text(self.serial_config.baud_rate.to_string())
+            .width(80.0)
+            .show_ui(ui, |ui| {
+                for baud_rate in BaudRate::iter() {
+                    ui.selectable_value(
+                        &mut self.serial_config.baud_rate,
+                        baud_rate as u32,
+                        baud_rate.to_string(),
+                    );
+                }
+            });
+        // 목록에 없는 속도는 직접 입력
+        ui.add(egui::DragValue::new(&mut self.serial_config.baud_rate).range(50..=4_000_000))
+            .on_hover_text("Custom baud rate");
+    }
+
+    // 열린 연결을 TCP server 로 공유
+    fn share_select(&mut self, ui: &mut egui::Ui) {
+        let Some(session) = &mut self.session else {
+            return;
+        };
+
+        ui.horizontal(|ui| {
+            ui.label("Share (TCP) :");
+            match session.share_status() {
+                Some((address, clients)) => {
+                    ui.label(format!("{} - {} client(s)", address, clients));
+                    if ui.button("Stop").clicked() {
+                        session.stop_share();
+                    }
+                }
+                None => {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.share_address)
+                            .hint_text("0.0.0.0:5000")
+                            .desired_width(160.0),
+                    )
+                    .on_hover_text("First client can write, the rest are read-only");
+                    if ui.button("Start").clicked() {
+                        self.share_error = session
+                            .start_share(&self.share_address)
+                            .err()
+                            .map(|e| format!("{} : {}", self.share_address, e));
+                    }
+                }
+            }
+        });
     }
 
     // COM Port 연결 설정 섹션
     fn section_comport_select(&mut self, ui: &mut egui::Ui) {
         egui::Frame::group(ui.style()).show(ui, |ui| {
             ui.horizontal(|ui| {
-                ui.label("Select COM Port :");
-                egui::ComboBox::from_id_salt("Select COM Port : ")
-                    .selected_text(format!("{:?}", self.com_port))
+                ui.label("Transport :");
+                egui::ComboBox::from_id_salt("Transport : ")
+                    .selected_text(self.transport_kind.to_string())
+                    .width(80.0)
                     .show_ui(ui, |ui| {
-                        for com_port in ComPort::iter() {
-                            ui.selectable_value(
-                                &mut self.com_port,
-                                com_port,
-                                format!("{:?}", com_port),
+                        for kind in TransportKind::iter() {
+                            ui.selectable_value(&mut self.transport_kind, kind, kind.to_string());
+                        }
+                    });
+
+                match self.transport_kind {
+                    TransportKind::Serial => self.serial_port_select(ui),
+                    TransportKind::Tcp | TransportKind::Rfc2217 => {
+                        ui.label("Address :");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.tcp_address)
+                                .hint_text("host:port")
+                                .desired_width(160.0),
+                        );
+                        // RFC 2217 은 원격 포트의 회선 설정도 보낸다
+                        if self.transport_kind == TransportKind::Rfc2217 {
+                            self.baud_rate_select(ui);
+                        }
+                    }
+                    TransportKind::Replay => {
+                        ui.label("File :");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.replay_path)
+                                .hint_text("capture.bin")
+                                .desired_width(160.0),
+                        );
+                    }
+                    TransportKind::Loopback => {}
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    match &self.session {
+                        Some(session) => {
+                            let lost = session.is_lost();
+                            let status = format!(
+                                "{} {}",
+                                if lost { "Reconnecting" } else { "Connected" },
+                                session.name()
                             );
+                            if ui.button("Disconnect").clicked() {
+                                self.disconnect();
+                            }
+                            if lost {
+                                ui.colored_label(Color32::YELLOW, status);
+                            } else {
+                                ui.colored_label(Color32::GREEN, status);
+                            }
+                        }
+                        None if self.connecting.is_some() => {
+                            if ui.button("Cancel").clicked() {
+                                self.connecting = None;
+                            }
+                            ui.spinner();
+                            ui.label("Connecting");
+                        }
+                        None => {
+                            if ui.button("Connect").clicked() {
+                                self.connect();
+                            }
+                            ui.label("Disconnected");
+                        }
+                    }
+
+                    if ui
+                        .checkbox(&mut self.auto_reconnect, "Auto-reconnect")
+                        .changed()
+                    {
+                        if let Some(session) = &self.session {
+                            session.set_auto_reconnect(self.auto_reconnect);
+                        }
+                    }
+                });
+            });
+
+            if matches!(
+                self.transport_kind,
+                TransportKind::Serial | TransportKind::Rfc2217
+            ) {
+                ui.horizontal(|ui| {
+                    let config = &mut self.serial_config;
+
+                    ui.label("Data bits :");
+                    egui::ComboBox::from_id_salt("Data bits : ")
+                        .selected_text(config.data_bits.to_string())
+                        .width(40.0)
+                        .show_ui(ui, |ui| {
+                            for data_bits in DataBits::iter() {
+                                ui.selectable_value(
+                                    &mut config.data_bits,
+                                    data_bits,
+                                    data_bits.to_string(),
+                                );
+                            }
+                        });
+
+                    ui.label("Parity :");
+                    egui::ComboBox::from_id_salt("Parity : ")
+                        .selected_text(config.parity.to_string())
+                        .width(60.0)
+                        .show_ui(ui, |ui| {
+                            for parity in Parity::iter() {
+                                ui.selectable_value(&mut config.parity, parity, parity.to_string());
+                            }
+                        });
+
+                    ui.label("Stop bits :");
+                    egui::ComboBox::from_id_salt("Stop bits : ")
+                        .selected_text(config.stop_bits.to_string())
+                        .width(40.0)
+                        .show_ui(ui, |ui| {
+                            for stop_bits in StopBits::iter() {
+                                ui.selectable_value(
+                                    &mut config.stop_bits,
+                                    stop_bits,
+                                    stop_bits.to_string(),
+                                );
+                            }
+                        });
+
+                    ui.label("Flow control :");
+                    egui::ComboBox::from_id_salt("Flow control : ")
+                        .selected_text(config.flow_control.to_string())
+                        .show_ui(ui, |ui| {
+                            for flow_control in FlowControl::iter() {
+                                ui.selectable_value(
+                                    &mut config.flow_control,
+                                    flow_control,
+                                    flow_control.to_string(),
+                                );
+                            }
+                        });
+                });
+            }
+
+            ui.horizontal(|ui| {
+                let mut changed = false;
+
+                ui.label("Frame spec :");
+                ui.label(&self.frame_spec.name);
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.frame_spec_path)
+                        .hint_text("spec.toml")
+                        .desired_width(160.0),
+                );
+                if ui.button("Load").clicked() {
+                    match FrameSpec::load(&self.frame_spec_path) {
+                        Ok(spec) => {
+                            self.frame_spec = spec;
+                            self.frame_spec_error = None;
+                            changed = true;
+                        }
+                        Err(e) => self.frame_spec_error = Some(e.to_string()),
+                    }
+                }
+                if ui.button("Default").clicked() {
+                    self.frame_spec = FrameSpec::default();
+                    self.frame_spec_error = None;
+                    changed = true;
+                }
+                if ui.button("Extended").on_hover_text("16-bit LEN").clicked() {
+                    self.frame_spec = FrameSpec::extended();
+                    self.frame_spec_error = None;
+                    changed = true;
+                }
+
+                ui.label("Checksum :");
+                egui::ComboBox::from_id_salt("Checksum : ")
+                    .selected_text(self.frame_spec.checksum.to_string())
+                    .show_ui(ui, |ui| {
+                        for checksum in ChecksumKind::iter() {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.frame_spec.checksum,
+                                    checksum,
+                                    checksum.to_string(),
+                                )
+                                .changed();
                         }
                     });
 
-                ui.label("Baud rate :");
-                egui::ComboBox::from_id_salt("Baud rate : ")
-                    .selected_text(self.baud_rate.to_string())
+                ui.label("Framing :");
+                egui::ComboBox::from_id_salt("Framing : ")
+                    .selected_text(self.frame_spec.framing.to_string())
                     .show_ui(ui, |ui| {
-                        for baud_rate in BaudRate::iter() {
-                            ui.selectable_value(
-                                &mut self.baud_rate,
-                                baud_rate,
-                                baud_rate.to_string(),
-                            );
+                        for framing in Framing::iter() {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.frame_spec.framing,
+                                    framing,
+                                    framing.to_string(),
+                                )
+                                .changed();
                         }
                     });
 
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    ui.button("Connect");
-                });
+                ui.label("Frame timeout (ms) :");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.frame_timeout_ms).range(0..=10_000))
+                    .on_hover_text("0 : disabled")
+                    .changed();
+
+                if let Some(session) = &self.session {
+                    let stats = session.decoder_stats();
+                    ui.separator();
+                    ui.label(format!(
+                        "Skipped : {} bytes  Resyncs : {}",
+                        stats.skipped_bytes, stats.resyncs
+                    ))
+                    .on_hover_text(format!(
+                        "Frames : {}\nCS errors : {}\nLEN errors : {}\nTimeouts : {}",
+                        stats.frames, stats.checksum_errors, stats.length_errors, stats.timeouts
+                    ));
+                }
+
+                if changed {
+                    self.apply_decoder_config();
+                }
             });
+
+            if self.session.is_some() {
+                self.share_select(ui);
+            }
+
+            if let Some(error) = &self.connect_error {
+                ui.colored_label(Color32::RED, error);
+            }
+            if let Some(error) = &self.share_error {
+                ui.colored_label(Color32::RED, error);
+            }
+            if let Some(error) = &self.frame_spec_error {
+                ui.colored_label(Color32::RED, error);
+            }
         });
     }
 
@@ -106,35 +739,57 @@ impl SerialApp {
             egui::CollapsingHeader::new("Filter Configuration")
                 .default_open(false)
                 .show(ui, |ui| {
+                    let mut changed = false;
                     ui.vertical(|ui: &mut egui::Ui| {
                         ui.horizontal(|ui| {
                             ui.label("ID      : ");
-                            ui.add_sized(
-                                ui.available_size(),
-                                egui::TextEdit::singleline(&mut self.id_filter),
-                            );
+                            changed |= ui
+                                .add_sized(
+                                    ui.available_size(),
+                                    egui::TextEdit::singleline(&mut self.id_filter)
+                                        .hint_text("C1, 10-1F, !05"),
+                                )
+                                .changed();
                         });
                         ui.horizontal(|ui| {
                             ui.label("CMD : ");
-                            ui.add_sized(
-                                ui.available_size(),
-                                egui::TextEdit::singleline(&mut self.cmd_filter),
-                            );
+                            changed |= ui
+                                .add_sized(
+                                    ui.available_size(),
+                                    egui::TextEdit::singleline(&mut self.cmd_filter),
+                                )
+                                .changed();
                         });
+                        ui.horizontal(|ui| {
+                            ui.label("Expr : ");
+                            changed |= ui
+                                .add_sized(
+                                    ui.available_size(),
+                                    egui::TextEdit::singleline(&mut self.expr_filter)
+                                        .hint_text("id == 0xC1 && data[0] > 0x10 && len > 8"),
+                                )
+                                .changed();
+                        });
+                        if let Some(error) = &self.filter_error {
+                            ui.colored_label(Color32::RED, error);
+                        }
                     });
+                    if changed {
+                        self.apply_filter();
+                    }
                 });
         });
     }
 
     fn unit_1(
-        &self,
         ui: &mut egui::Ui,
         label: &str,
         value: &mut String,
         align_center: bool,
         size: f32,
-    ) -> InnerResponse<()> {
-        return ui.allocate_ui(vec2(size, 50.0), |ui| {
+        enabled: bool,
+    ) -> egui::Response {
+        ui.allocate_ui(vec2(size, 50.0), |ui| {
             ui.with_layout(
                 egui::Layout::from_main_dir_and_cross_align(
                     egui::Direction::TopDown,
@@ -153,23 +808,371 @@ impl SerialApp {
                     //     Color32::from_rgba_unmultiplied(255, 0, 0, 128), // Example of a faded red color
                     // );
                     ui.label(label);
-                    ui.add(
+                    ui.add_enabled(
+                        enabled,
                         egui::TextEdit::singleline(value).horizontal_align(if align_center {
                             Align::Center
                         } else {
                             Align::Min
                         }),
-                    );
+                    )
                 },
-            );
+            )
+            .inner
+        })
+        .inner
+    }
+
+    // 전송 입력으로 패킷을 만든다. LEN, CS 는 수동 입력이 아니면 계산한다
+    fn send_packet(&self) -> Result<PACKET, String> {
+        let byte = |label: &str, text: &str| match parse_hex(text).as_deref() {
+            Some(&[value]) => Ok(value),
+            _ => Err(format!("{} : one hex byte expected", label)),
+        };
+        // 필드 폭 (1 또는 2 바이트) 을 넘는 값은 잘라내지 않고 오류로 알린다
+        let sized = |label: &str, text: &str, width: usize| {
+            let value = u16::from_str_radix(text.trim(), 16)
+                .map_err(|_| format!("{} : invalid hex", label))?;
+            if width == 1 && value > 0xFF {
+                return Err(format!("{} : {:X} does not fit in one byte", label, value));
+            }
+            Ok(value)
+        };
+
+        let id = byte("ID", &self.send_id)?;
+        let command = byte("CMD", &self.send_cmd)?;
+        let sequence = if self.frame_spec.fields.contains(&Field::Sequence) {
+            byte("SEQ", &self.send_seq)?
+        } else {
+            0
+        };
+        let data = parse_hex(&self.send_data).ok_or("DATA : invalid hex")?;
+
+        let mut packet = PacketBuilder::new()
+            .spec(&self.frame_spec)
+            .id(id)
+            .command(command)
+            .sequence(sequence)
+            .payload(&data)
+            .build()
+            .map_err(|e| format!("DATA : {}", e))?;
+
+        // LEN 만 바꾼 경우 CS 는 바뀐 LEN 으로 다시 계산한다
+        if self.send_len_manual {
+            packet.header.length =
+                sized("LEN", &self.send_len, self.frame_spec.length_width as usize)?;
+            packet.checksum = packet.expected_cs();
+        }
+        if self.send_cs_manual {
+            packet.checksum = sized("CS", &self.send_cs, self.frame_spec.checksum.width())?;
+        }
+
+        Ok(packet)
+    }
+
+    // 열린 연결로 보내고 로그에 TX 로 남긴다
+    fn send(&mut self, packet: &PACKET) {
+        let Some(session) = &self.session else {
+            return;
+        };
+
+        match session.write(&packet.to_wire()) {
+            Ok(()) => {
+                debug!("Packet Sent\r\n{}", packet.to_string());
+                self.packet_log
+                    .push(LogEntry::from_packet(Direction::Tx, packet));
+                self.send_error = None;
+            }
+            Err(e) => self.send_error = Some(e.to_string()),
+        }
+    }
+
+    // Count 가 1 이면 바로 보내고, 아니면 Delay 간격으로 반복 전송을 시작한다
+    fn start_send(&mut self, packet: PACKET) {
+        if self.send_count == 1 {
+            self.send(&packet);
+            if self.send_error.is_none() {
+                self.advance_sequence(1);
+            }
+            return;
+        }
+
+        let has_sequence = self.frame_spec.fields.contains(&Field::Sequence);
+        let schedule = Schedule {
+            packet,
+            interval: Duration::from_millis(self.send_delay as u64),
+            count: (self.send_count > 0).then_some(self.send_count),
+            increment_sequence: self.send_auto_seq && has_sequence,
+            recompute_checksum: !self.send_cs_manual,
+        };
+        if let Some(session) = &mut self.session {
+            self.send_error = None;
+            session.start_schedule(schedule);
+        }
+    }
+
+    // Auto SEQ 면 보낸 수만큼 SEQ 입력을 올려서 다음 전송이 이어지게 한다
+    fn advance_sequence(&mut self, sent: u32) {
+        if !self.send_auto_seq || !self.frame_spec.fields.contains(&Field::Sequence) {
+            return;
+        }
+        if let Some(&[sequence]) = parse_hex(&self.send_seq).as_deref() {
+            self.send_seq = format!("{:02X}", sequence.wrapping_add(sent as u8));
+        }
+    }
+
+    // 명령 목록의 명령을 지금 SEQ 로 보낸다
+    fn send_command(&mut self, index: usize) {
+        let Some(command) = self.library.commands.get(index) else {
+            return;
+        };
+        let sequence = match parse_hex(&self.send_seq).as_deref() {
+            Some(&[sequence]) => sequence,
+            _ => 0,
+        };
+
+        match command.build(&self.frame_spec, sequence) {
+            Ok(packet) => {
+                self.send(&packet);
+                if self.send_error.is_none() {
+                    self.advance_sequence(1);
+                }
+            }
+            Err(e) => self.send_error = Some(e.to_string()),
+        }
+    }
+
+    // 단축키가 눌린 명령을 보낸다. 연결이 없거나 입력 칸에 글자를 쓰는 중이면 키를 가로채지 않는다
+    fn library_shortcuts(&mut self, ctx: &egui::Context) {
+        if self.session.is_none() || ctx.wants_keyboard_input() {
+            return;
+        }
+        let pressed: Vec<usize> = ctx.input_mut(|input| {
+            self.library
+                .commands
+                .iter()
+                .enumerate()
+                .filter_map(|(index, command)| {
+                    let shortcut = command.keyboard_shortcut()?;
+                    input.consume_shortcut(&shortcut).then_some(index)
+                })
+                .collect()
+        });
+        for index in pressed {
+            self.send_command(index);
+        }
+    }
+
+    // 전송 입력을 새 명령으로 저장하고 바로 편집한다
+    fn save_send_as_command(&mut self) {
+        let group = self
+            .library_editing
+            .and_then(|index| self.library.commands.get(index))
+            .map_or(String::from("Default"), |command| command.group.clone());
+        self.library.commands.push(Command {
+            name: format!("Command {}", self.library.commands.len() + 1),
+            group,
+            id: self.send_id.clone(),
+            command: self.send_cmd.clone(),
+            payload: self.send_data.clone(),
+            shortcut: String::new(),
+        });
+        self.library_editing = Some(self.library.commands.len() - 1);
+    }
+
+    // 명령을 전송 입력으로 옮긴다. 자리표시자는 지금 SEQ 로 채운다
+    fn load_command(&mut self, index: usize) {
+        let Some(command) = self.library.commands.get(index) else {
+            return;
+        };
+        let sequence = match parse_hex(&self.send_seq).as_deref() {
+            Some(&[sequence]) => sequence,
+            _ => 0,
+        };
+        self.send_id = command.id.clone();
+        self.send_cmd = command.command.clone();
+        self.send_data = match command.expand_payload(sequence) {
+            Ok(payload) => payload
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<_>>()
+                .join(" "),
+            Err(_) => command.payload.clone(),
+        };
+    }
+
+    fn import_library(&mut self) {
+        self.library_status = Some(match Library::load(&self.library_path) {
+            Ok(library) => {
+                let count = library.commands.len();
+                self.library.merge(library);
+                self.library_editing = None;
+                Ok(format!("{} commands imported", count))
+            }
+            Err(e) => Err(e.to_string()),
+        });
+    }
+
+    fn export_library(&mut self) {
+        self.library_status = Some(match self.library.save(&self.library_path) {
+            Ok(()) => Ok(format!(
+                "{} commands saved to {}",
+                self.library.commands.len(),
+                self.library_path
+            )),
+            Err(e) => Err(e.to_string()),
+        });
+    }
+
+    // 명령 목록 섹션
+    fn section_library(&mut self, ui: &mut egui::Ui) {
+        egui::Frame::group(ui.style()).show(ui, |ui| {
+            egui::CollapsingHeader::new("Command Library")
+                .default_open(false)
+                .show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        if ui.button("Save current").clicked() {
+                            self.save_send_as_command();
+                        }
+                        ui.separator();
+                        ui.label("File :");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut self.library_path)
+                                .desired_width(200.0)
+                                .hint_text("commands.json / commands.toml"),
+                        );
+                        if ui.button("Import").clicked() {
+                            self.import_library();
+                        }
+                        if ui.button("Export").clicked() {
+                            self.export_library();
+                        }
+                        match &self.library_status {
+                            Some(Ok(message)) => {
+                                ui.colored_label(Color32::GREEN, message);
+                            }
+                            Some(Err(error)) => {
+                                ui.colored_label(Color32::RED, error);
+                            }
+                            None => {}
+                        }
+                    });
+
+                    if self.library.commands.is_empty() {
+                        ui.label("No commands saved");
+                        return;
+                    }
+
+                    let connected = self.session.is_some();
+                    let mut send = None;
+                    let mut load = None;
+                    let mut remove = None;
+                    let groups: Vec<String> = self
+                        .library
+                        .groups()
+                        .into_iter()
+                        .map(String::from)
+                        .collect();
+                    for group in groups {
+                        let title = if group.is_empty() {
+                            "(no group)"
+                        } else {
+                            group.as_str()
+                        };
+                        egui::CollapsingHeader::new(title)
+                            .id_salt(("library group", &group))
+                            .default_open(true)
+                            .show(ui, |ui| {
+                                for (index, command) in self.library.commands.iter_mut().enumerate()
+                                {
+                                    if command.group != group {
+                                        continue;
+                                    }
+                                    ui.horizontal(|ui| {
+                                        if ui.add_enabled(connected, Button::new("Send")).clicked()
+                                        {
+                                            send = Some(index);
+                                        }
+                                        ui.label(RichText::new(&command.name).strong());
+                                        ui.label(
+                                            RichText::new(format!(
+                                                "{} {} [{}]",
+                                                command.id, command.command, command.payload
+                                            ))
+                                            .monospace(),
+                                        );
+                                        if !command.shortcut.is_empty() {
+                                            let shortcut = if command.keyboard_shortcut().is_some()
+                                            {
+                                                RichText::new(&command.shortcut).weak()
+                                            } else {
+                                                RichText::new(&command.shortcut).color(Color32::RED)
+                                            };
+                                            ui.label(shortcut);
+                                        }
+                                        ui.with_layout(
+                                            Layout::right_to_left(Align::Center),
+                                            |ui| {
+                                                if ui.button("✖").on_hover_text("Remove").clicked()
+                                                {
+                                                    remove = Some(index);
+                                                }
+                                                let mut editing =
+                                                    self.library_editing == Some(index);
+                                                if ui.toggle_value(&mut editing, "Edit").changed() {
+                                                    self.library_editing = editing.then_some(index);
+                                                }
+                                                if ui
+                                                    .button("Load")
+                                                    .on_hover_text("Copy to Packet Send")
+                                                    .clicked()
+                                                {
+                                                    load = Some(index);
+                                                }
+                                            },
+                                        );
+                                    });
+
+                                    if self.library_editing == Some(index) {
+                                        command_editor(ui, index, command);
+                                    }
+                                }
+                            });
+                    }
+
+                    if let Some(index) = send {
+                        self.send_command(index);
+                    }
+                    if let Some(index) = load {
+                        self.load_command(index);
+                    }
+                    if let Some(index) = remove {
+                        self.library.commands.remove(index);
+                        self.library_editing = None;
+                    }
+                });
         });
     }
 
     // 패킷 전송 섹션
-    fn section_send_packet(&self, ui: &mut egui::Ui) {
-        let mut data = String::new();
-        let mut delay = String::new();
-        let mut count = String::new();
+    fn section_send_packet(&mut self, ui: &mut egui::Ui) {
+        let has_sequence = self.frame_spec.fields.contains(&Field::Sequence);
+        let packet = self.send_packet();
+        // 수동 입력이 아니면 계산한 값을 흐리게 보여준다
+        if let Ok(packet) = &packet {
+            if !self.send_len_manual {
+                self.send_len = match self.frame_spec.length_width {
+                    2 => format!("{:04X}", packet.header.length),
+                    _ => format!("{:02X}", packet.header.length),
+                };
+            }
+            if !self.send_cs_manual {
+                self.send_cs = match self.frame_spec.checksum.width() {
+                    1 => format!("{:02X}", packet.checksum),
+                    _ => format!("{:04X}", packet.checksum),
+                };
+            }
+        }
 
         egui::Frame::group(ui.style()).show(ui, |ui| {
             egui::CollapsingHeader::new("Packet Send")
@@ -182,57 +1185,72 @@ impl SerialApp {
                         //     Color32::from_rgba_unmultiplied(0, 255, 0, 128), // Example of a faded red color
                         // );
                         ui.horizontal(|ui| {
-                            self.unit_1(
-                                ui,
-                                "STX",
-                                &mut format!("{:02X}", self.packet.header.stx),
-                                true,
-                                40.0,
-                            );
-                            self.unit_1(
-                                ui,
-                                "ID",
-                                &mut format!("{:02X}", self.packet.header.id),
-                                true,
-                                40.0,
-                            );
-                            self.unit_1(
-                                ui,
-                                "LEN",
-                                &mut format!("{:02X}", self.packet.header.length),
-                                true,
-                                40.0,
-                            );
-                            self.unit_1(
-                                ui,
-                                "CMD",
-                                &mut &mut format!("{:02X}", self.packet.header.command),
-                                true,
-                                40.0,
-                            );
-                            self.unit_1(
-                                ui,
-                                "SEQ",
-                                &mut &mut format!("{:02X}", self.packet.header.sequence),
-                                true,
-                                40.0,
-                            );
+                            let mut sync: String = self
+                                .frame_spec
+                                .sync
+                                .iter()
+                                .map(|byte| format!("{:02X}", byte))
+                                .collect();
+                            let sync_width = 20.0 + 20.0 * self.frame_spec.sync.len() as f32;
+                            Self::unit_1(ui, "STX", &mut sync, true, sync_width, false);
 
-                            self.unit_1(
+                            // 헤더 필드는 frame spec 순서대로
+                            for field in self.frame_spec.fields.clone() {
+                                match field {
+                                    Field::Id => {
+                                        Self::unit_1(ui, "ID", &mut self.send_id, true, 40.0, true);
+                                    }
+                                    Field::Length => {
+                                        Self::unit_1(
+                                            ui,
+                                            "LEN",
+                                            &mut self.send_len,
+                                            true,
+                                            20.0 + 20.0 * self.frame_spec.length_width as f32,
+                                            self.send_len_manual,
+                                        );
+                                    }
+                                    Field::Command => {
+                                        Self::unit_1(
+                                            ui,
+                                            "CMD",
+                                            &mut self.send_cmd,
+                                            true,
+                                            40.0,
+                                            true,
+                                        );
+                                    }
+                                    Field::Sequence => {
+                                        Self::unit_1(
+                                            ui,
+                                            "SEQ",
+                                            &mut self.send_seq,
+                                            true,
+                                            40.0,
+                                            true,
+                                        );
+                                    }
+                                }
+                            }
+
+                            let cs_width = 20.0 + 20.0 * self.frame_spec.checksum.width() as f32;
+                            Self::unit_1(
                                 ui,
                                 "DATA",
-                                &mut data,
+                                &mut self.send_data,
                                 false,
-                                ui.available_width() - (50.0),
+                                ui.available_width() - (cs_width + 10.0),
+                                true,
                             );
 
                             ui.with_layout(egui::Layout::right_to_left(egui::Align::Min), |ui| {
-                                self.unit_1(
+                                Self::unit_1(
                                     ui,
                                     "CS",
-                                    &mut &mut format!("{:02X}", self.packet.checksum),
+                                    &mut self.send_cs,
                                     true,
-                                    40.0,
+                                    cs_width,
+                                    self.send_cs_manual,
                                 );
                             });
                         });
@@ -241,47 +1259,197 @@ impl SerialApp {
                             ui.label("Delay :");
                             ui.add_sized(
                                 [80.0, 20.0],
-                                egui::TextEdit::singleline(&mut delay)
-                                    .horizontal_align(Align::Center),
+                                egui::DragValue::new(&mut self.send_delay)
+                                    .range(0..=3_600_000)
+                                    .suffix(" ms"),
                             );
                             ui.label("Count :");
                             ui.add_sized(
                                 [80.0, 20.0],
-                                egui::TextEdit::singleline(&mut count)
-                                    .horizontal_align(Align::Center),
+                                egui::DragValue::new(&mut self.send_count).custom_formatter(
+                                    |count, _| {
+                                        if count == 0.0 {
+                                            String::from("∞")
+                                        } else {
+                                            count.to_string()
+                                        }
+                                    },
+                                ),
+                            )
+                            .on_hover_text("0 : until stopped");
+                            ui.add_enabled(
+                                has_sequence,
+                                egui::Checkbox::new(&mut self.send_auto_seq, "Auto SEQ"),
                             );
+                            // 일부러 깨진 프레임을 보낼 때 직접 입력한다
+                            ui.checkbox(&mut self.send_len_manual, "Manual LEN");
+                            ui.checkbox(&mut self.send_cs_manual, "Manual CS");
                             ui.with_layout(
                                 egui::Layout::right_to_left(egui::Align::Center),
                                 |ui| {
-                                    ui.add_sized([40.0, 20.0], Button::new("Send"));
+                                    let progress = self
+                                        .session
+                                        .as_ref()
+                                        .and_then(|session| session.schedule_progress());
+                                    match progress {
+                                        Some((sent, count)) => {
+                                            if ui
+                                                .add_sized([40.0, 20.0], Button::new("Stop"))
+                                                .clicked()
+                                            {
+                                                if let Some(session) = &mut self.session {
+                                                    session.stop_schedule();
+                                                }
+                                            }
+                                            match count {
+                                                Some(count) => {
+                                                    ui.add(
+                                                        egui::ProgressBar::new(
+                                                            sent as f32 / count as f32,
+                                                        )
+                                                        .desired_width(120.0)
+                                                        .text(format!("{} / {}", sent, count)),
+                                                    );
+                                                }
+                                                None => {
+                                                    ui.label(format!("{} sent", sent));
+                                                }
+                                            }
+                                        }
+                                        None => {
+                                            let enabled = self.session.is_some() && packet.is_ok();
+                                            if ui
+                                                .add_enabled(enabled, Button::new("Send"))
+                                                .clicked()
+                                            {
+                                                if let Ok(packet) = packet.clone() {
+                                                    self.start_send(packet);
+                                                }
+                                            }
+                                        }
+                                    }
                                 },
                             );
                         });
+
+                        if let Err(error) = &packet {
+                            ui.colored_label(Color32::RED, error);
+                        }
+                        if let Some(error) = &self.send_error {
+                            ui.colored_label(Color32::RED, error);
+                        }
                     });
                 });
         });
     }
 
     // 로그 출력 섹션
-    fn log(&self, ui: &mut egui::Ui) {
+    fn log(&mut self, ui: &mut egui::Ui) {
         egui::Frame::group(ui.style()).show(ui, |ui| {
             let width: f32 = ui.available_width(); // 사용 가능한 전체 너비 가져오기
             ui.set_min_width(width); // Frame의 최소 너비를 설정
+
+            ui.horizontal(|ui| {
+                let mut paused = self.packet_log.is_paused();
+                if ui.toggle_value(&mut paused, "Pause").changed() {
+                    self.packet_log.set_paused(paused);
+                }
+                ui.checkbox(&mut self.log_auto_scroll, "Auto-scroll");
+                if ui.button("Clear").clicked() {
+                    self.packet_log.clear();
+                }
+                if self.packet_log.filter().is_empty() {
+                    ui.label(format!("{} rows", self.packet_log.len()));
+                } else {
+                    ui.label(format!(
+                        "{} of {} rows",
+                        self.packet_log.visible_len(),
+                        self.packet_log.len()
+                    ));
+                }
+                if self.packet_log.held() > 0 {
+                    ui.colored_label(
+                        Color32::YELLOW,
+                        format!("{} new while paused", self.packet_log.held()),
+                    );
+                }
+            });
+
+            ui.spacing_mut().item_spacing = vec2(0.0, 0.0);
+            ui.label(
+                RichText::new(log_columns(
+                    "TIME", "DIR", "ID", "CMD", "SEQ", "LEN", "CS", "DATA",
+                ))
+                .monospace()
+                .strong(),
+            );
+
+            // 보이는 행만 그리므로 행이 많아도 느려지지 않는다
+            let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
             egui::ScrollArea::vertical()
                 .auto_shrink(false)
-                .scroll_bar_visibility(egui::scroll_area::ScrollBarVisibility::default())
-                .show(ui, |ui| {
-                    ui.with_layout(
-                        egui::Layout::top_down(egui::Align::LEFT).with_cross_justify(true),
-                        |ui| {
-                            ui.label(lipsum(1000));
-                        },
-                    );
+                .stick_to_bottom(self.log_auto_scroll && !self.packet_log.is_paused())
+                .show_rows(ui, row_height, self.packet_log.visible_len(), |ui, rows| {
+                    for row in rows {
+                        if let Some(entry) = self.packet_log.visible(row) {
+                            log_row(ui, entry);
+                        }
+                    }
                 });
         });
     }
 }
 
+// 헤더와 행이 같은 폭을 쓰도록 한 곳에서 맞춘다
+#[allow(clippy::too_many_arguments)]
+fn log_columns(
+    time: &str,
+    direction: &str,
+    id: &str,
+    command: &str,
+    sequence: &str,
+    length: &str,
+    status: &str,
+    data: &str,
+) -> String {
+    format!(
+        "{:<12}  {:<3}  {:<3}{:<4}{:<4}{:>4}  {:<9}  {}",
+        time, direction, id, command, sequence, length, status, data
+    )
+}
+
+fn log_row(ui: &mut egui::Ui, entry: &LogEntry) {
+    if let Some(note) = &entry.note {
+        let text = format!(
+            "{:<12}  ---------- {} ----------",
+            entry.time.format("%H:%M:%S%.3f"),
+            note
+        );
+        ui.add(egui::Label::new(RichText::new(text).monospace().color(Color32::YELLOW)).truncate());
+        return;
+    }
+
+    let text = log_columns(
+        &entry.time.format("%H:%M:%S%.3f").to_string(),
+        &entry.direction.to_string(),
+        &format!("{:02X}", entry.id),
+        &format!("{:02X}", entry.command),
+        &entry
+            .sequence
+            .map_or(String::from("--"), |sequence| format!("{:02X}", sequence)),
+        &format!("{:02X}", entry.length),
+        &entry.status.to_string(),
+        entry.payload_hex(),
+    );
+    let color = match entry.status {
+        ChecksumStatus::Ok => ui.visuals().text_color(),
+        ChecksumStatus::Mismatch { .. } => Color32::RED,
+    };
+
+    // 긴 DATA 는 잘라서 보이고, 마우스를 올리면 전체를 보여준다
+    ui.add(egui::Label::new(RichText::new(text).monospace().color(color)).truncate());
+}
+
 impl eframe::App for SerialApp {
     /// Called by the frame work to save state before shutdown.
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
@@ -298,6 +1466,14 @@ impl eframe::App for SerialApp {
         style.spacing.item_spacing = egui::vec2(10.0, 10.0); // 위젯 사이의 간격
         ctx.set_style(style);
 
+        self.poll_connect();
+        self.poll_session();
+        self.library_shortcuts(ctx);
+        if self.session.is_some() || self.connecting.is_some() {
+            // 입력이 없어도 수신 쓰레드의 알림을 확인하도록 주기적으로 다시 그린다
+            ctx.request_repaint_after(Duration::from_millis(100));
+        }
+
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             // The top panel is often a good place for a menu bar:
 
@@ -306,15 +1482,25 @@ impl eframe::App for SerialApp {
                     if ui.button("Exit").clicked() {
                         ctx.send_viewport_cmd(egui::ViewportCommand::Close);
                     }
-                    if ui.button("Save log as").clicked() {}
+                    if ui.button("Save log as").clicked() {
+                        self.export_open = true;
+                        self.export_status = None;
+                        ui.close_menu();
+                    }
+                });
+                ui.menu_button("View", |ui| {
+                    if ui.button("Clear log").clicked() {
+                        self.packet_log.clear();
+                    }
                 });
-                ui.menu_button("View", |ui| if ui.button("Clear log").clicked() {});
                 ui.menu_button("Option", |ui| if ui.button("Bit checker").clicked() {});
                 ui.menu_button("Help", |ui| if ui.button("About").clicked() {});
                 // egui::widgets::global_theme_preference_buttons(ui);
             });
         });
 
+        self.export_window(ctx);
+
         egui::CentralPanel::default().show(ctx, |ui| {
             // The central panel the region left after adding TopPanel's and SidePanel's
             egui::Frame::default()
@@ -323,6 +1509,7 @@ impl eframe::App for SerialApp {
                     self.section_comport_select(ui);
                     self.section_filter_config(ui);
                     self.section_send_packet(ui);
+                    self.section_library(ui);
                     self.log(ui);
                 });
 
@@ -334,6 +1521,40 @@ impl eframe::App for SerialApp {
     }
 }
 
+// 명령 하나의 이름, 그룹, 프레임, 단축키 입력
+fn command_editor(ui: &mut egui::Ui, index: usize, command: &mut Command) {
+    egui::Grid::new(("library editor", index))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label("Name :");
+            ui.text_edit_singleline(&mut command.name);
+            ui.end_row();
+            ui.label("Group :");
+            ui.text_edit_singleline(&mut command.group);
+            ui.end_row();
+            ui.label("ID :");
+            ui.add(egui::TextEdit::singleline(&mut command.id).desired_width(40.0));
+            ui.end_row();
+            ui.label("CMD :");
+            ui.add(egui::TextEdit::singleline(&mut command.command).desired_width(40.0));
+            ui.end_row();
+            ui.label("Payload :");
+            ui.add(
+                egui::TextEdit::singleline(&mut command.payload)
+                    .desired_width(300.0)
+                    .hint_text("01 02 {seq} {u16:1000} {time}"),
+            );
+            ui.end_row();
+            ui.label("Shortcut :");
+            ui.add(
+                egui::TextEdit::singleline(&mut command.shortcut)
+                    .desired_width(120.0)
+                    .hint_text("Ctrl+1"),
+            );
+            ui.end_row();
+        });
+}
+
 // fn powered_by_egui_and_eframe(ui: &mut egui::Ui) {
 //     ui.horizontal(|ui: &mut egui::Ui| {
 //         ui.spacing_mut().item_spacing.x = 0.0;
@@ -0,0 +1,376 @@
+//! 장치 없이 GUI 를 시험할 수 있는 펌웨어 흉내 프로그램.
+//!
+//! pty (또는 TCP) 를 열고, 받은 명령에 스크립트대로 응답하고, 주기적으로 telemetry 프레임을 보낸다.
+//!
+//! ```text
+//! simulator [--pty | --tcp <ADDR>] [--id <ID>] [--script <FILE>] [--spec <FILE>]
+//!           [--telemetry <MS>] [--telemetry-cmd <CMD>] [--corrupt <PERCENT>] [--truncate <PERCENT>]
+//! ```
+//!
+//! 스크립트 (TOML) 예 :
+//!
+//! ```toml
+//! [[reply]]
+//! command = 0x12          # 이 CMD 를 받으면
+//! response = 0x92         # 이 CMD 로 응답. 없으면 CMD | 0x80
+//! payload = [0x00, 0x01]  # 이 DATA 로 응답. 없으면 받은 DATA 를 그대로
+//! ```
+//!
+//! 스크립트에 없는 명령은 CMD | 0x80 으로 받은 DATA 를 그대로 돌려준다.
+#![allow(non_snake_case)]
+
+use log::{debug, error, info, warn, LevelFilter};
+use log4rs::{
+    append::console::ConsoleAppender,
+    config::{Appender, Config, Root},
+    encode::pattern::PatternEncoder,
+};
+use serde::Deserialize;
+use std::{
+    fs,
+    net::TcpListener,
+    process, thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use RUST_tutorial::protocol::{Decoder, FrameSpec, PacketBuilder, PACKET};
+use RUST_tutorial::serial::SerialError;
+use RUST_tutorial::transport::{TcpTransport, Transport};
+
+const LOG_PATTERN: &str = "[{d(%H:%M:%S%.3f)} {l}] {m}{n}";
+const USAGE: &str = "usage: simulator [--pty | --tcp <ADDR>] [--id <ID>] [--script <FILE>] \
+[--spec <FILE>] [--telemetry <MS>] [--telemetry-cmd <CMD>] [--corrupt <PERCENT>] [--truncate <PERCENT>]";
+
+// 응답 CMD 를 지정하지 않았을 때 요청 CMD 에 더하는 비트
+const RESPONSE_BIT: u8 = 0x80;
+
+#[derive(Debug, Clone, Deserialize)]
+struct ScriptReply {
+    command: u8,
+    response: Option<u8>,
+    payload: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Script {
+    #[serde(default)]
+    reply: Vec<ScriptReply>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Listen {
+    Pty,
+    Tcp(String),
+}
+
+#[derive(Debug, Clone)]
+struct Args {
+    listen: Listen,
+    id: u8,
+    script: Script,
+    spec: FrameSpec,
+    // 0 이면 보내지 않음
+    telemetry: Duration,
+    telemetry_cmd: u8,
+    // 보내는 프레임 중 CS 를 틀리게 / 중간에 자를 비율 (%)
+    corrupt: u32,
+    truncate: u32,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            listen: Listen::Pty,
+            id: 0xC1,
+            script: Script::default(),
+            spec: FrameSpec::default(),
+            telemetry: Duration::from_millis(1000),
+            telemetry_cmd: 0x50,
+            corrupt: 0,
+            truncate: 0,
+        }
+    }
+}
+
+// 0x 로 시작하면 16진수
+fn parse_number(text: &str) -> Result<u32, String> {
+    let parsed = match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("invalid number : {}", text))
+}
+
+fn parse_u8(text: &str) -> Result<u8, String> {
+    u8::try_from(parse_number(text)?).map_err(|_| format!("out of range : {}", text))
+}
+
+fn parse_args() -> Result<Args, String> {
+    let mut args = Args::default();
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        let mut value = || iter.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--pty" => args.listen = Listen::Pty,
+            "--tcp" => args.listen = Listen::Tcp(value()?),
+            "--id" => args.id = parse_u8(&value()?)?,
+            "--script" => {
+                let path = value()?;
+                let text = fs::read_to_string(&path).map_err(|e| format!("{} : {}", path, e))?;
+                args.script = toml::from_str(&text).map_err(|e| format!("{} : {}", path, e))?;
+            }
+            "--spec" => {
+                let path = value()?;
+                args.spec = FrameSpec::load(&path).map_err(|e| format!("{} : {}", path, e))?;
+            }
+            "--telemetry" => {
+                args.telemetry = Duration::from_millis(parse_number(&value()?)? as u64)
+            }
+            "--telemetry-cmd" => args.telemetry_cmd = parse_u8(&value()?)?,
+            "--corrupt" => args.corrupt = parse_number(&value()?)?.min(100),
+            "--truncate" => args.truncate = parse_number(&value()?)?.min(100),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument : {}", arg)),
+        }
+    }
+
+    Ok(args)
+}
+
+// 손상 주입에만 쓰는 xorshift. 재현성은 필요 없다
+struct Rng(u64);
+
+impl Rng {
+    fn new() -> Rng {
+        let seed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        Rng(seed | 1)
+    }
+
+    fn percent(&mut self, percent: u32) -> bool {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % 100) < percent as u64
+    }
+}
+
+struct Simulator {
+    args: Args,
+    decoder: Decoder,
+    rng: Rng,
+    telemetry_seq: u8,
+    started: Instant,
+    last_telemetry: Instant,
+}
+
+fn new_decoder(spec: &FrameSpec) -> Decoder {
+    let mut decoder = Decoder::new();
+    decoder.set_spec(spec);
+    decoder.set_resync(true);
+    decoder
+}
+
+impl Simulator {
+    fn new(args: Args) -> Simulator {
+        Simulator {
+            decoder: new_decoder(&args.spec),
+            args,
+            rng: Rng::new(),
+            telemetry_seq: 0,
+            started: Instant::now(),
+            last_telemetry: Instant::now(),
+        }
+    }
+
+    /// transport 가 끊길 때까지 응답과 telemetry 를 보낸다
+    fn run(&mut self, transport: &mut dyn Transport) -> Result<(), SerialError> {
+        // 이전 client 가 보내다 만 프레임은 버린다
+        self.decoder = new_decoder(&self.args.spec);
+
+        loop {
+            let bytes = transport.read()?;
+            let results: Vec<_> = self.decoder.feed(&bytes).collect();
+            for result in results {
+                match result {
+                    Ok(request) => {
+                        let reply = self.reply(&request);
+                        self.send(transport, reply)?;
+                    }
+                    Err(e) => warn!("Request dropped : {}", e),
+                }
+            }
+
+            if !self.args.telemetry.is_zero()
+                && self.last_telemetry.elapsed() >= self.args.telemetry
+            {
+                self.last_telemetry = Instant::now();
+                let telemetry = self.telemetry();
+                self.send(transport, telemetry)?;
+            }
+        }
+    }
+
+    fn reply(&self, request: &PACKET) -> PACKET {
+        let command = request.header.command;
+        let script = self
+            .args
+            .script
+            .reply
+            .iter()
+            .find(|reply| reply.command == command);
+        let response = script
+            .and_then(|reply| reply.response)
+            .unwrap_or(command | RESPONSE_BIT);
+        let payload = script
+            .and_then(|reply| reply.payload.as_deref())
+            .unwrap_or(request.payload());
+        debug!(
+            "Request CMD {:02X} SEQ {:02X} -> CMD {:02X}",
+            command, request.header.sequence, response
+        );
+
+        self.build(response, request.header.sequence, payload)
+    }
+
+    // DATA : 시작 후 경과 시간 (ms, big-endian 4 byte)
+    fn telemetry(&mut self) -> PACKET {
+        let uptime = self.started.elapsed().as_millis() as u32;
+        let sequence = self.telemetry_seq;
+        self.telemetry_seq = self.telemetry_seq.wrapping_add(1);
+
+        self.build(self.args.telemetry_cmd, sequence, &uptime.to_be_bytes())
+    }
+
+    fn build(&self, command: u8, sequence: u8, payload: &[u8]) -> PACKET {
+        let max = self.args.spec.max_payload();
+        PacketBuilder::new()
+            .spec(&self.args.spec)
+            .id(self.args.id)
+            .command(command)
+            .sequence(sequence)
+            .payload(&payload[..payload.len().min(max)])
+            .build()
+            .expect("payload is clamped to max_payload")
+    }
+
+    // 설정한 비율로 CS 를 틀리게 하거나 프레임을 잘라서 보낸다
+    fn send(&mut self, transport: &mut dyn Transport, packet: PACKET) -> Result<(), SerialError> {
+        let mut frame = packet.serialize();
+        if self.rng.percent(self.args.corrupt) {
+            if let Some(cs) = frame.last_mut() {
+                *cs ^= 0xFF;
+            }
+            info!("Injected bad checksum : {:02X?}", frame);
+        } else if self.rng.percent(self.args.truncate) {
+            frame.truncate(frame.len() / 2);
+            info!("Injected truncated frame : {:02X?}", frame);
+        }
+
+        transport.write(&self.args.spec.framing.encode(&frame))
+    }
+}
+
+#[cfg(unix)]
+fn run_pty(simulator: &mut Simulator) -> Result<(), String> {
+    use serialport::{SerialPort, TTYPort};
+    use RUST_tutorial::serial::SERIAL;
+
+    let (master, slave) = TTYPort::pair().map_err(|e| format!("cannot open pty : {}", e))?;
+    let slave_name = slave.name().unwrap_or_default();
+    let mut transport = SERIAL::from_port(Box::new(master)).map_err(|e| e.to_string())?;
+    info!("Simulator listening on pty : {}", slave_name);
+    println!("{}", slave_name);
+
+    // slave 를 열어 둬야 GUI 가 아직 열지 않았을 때 master 읽기가 EIO 로 끝나지 않는다
+    let _slave = slave;
+    loop {
+        if let Err(e) = simulator.run(&mut transport) {
+            debug!("pty read : {}", e);
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn run_pty(_simulator: &mut Simulator) -> Result<(), String> {
+    Err(String::from(
+        "pty is not supported on this platform, use --tcp",
+    ))
+}
+
+fn run_tcp(simulator: &mut Simulator, address: &str) -> Result<(), String> {
+    let listener = TcpListener::bind(address).map_err(|e| format!("{} : {}", address, e))?;
+    info!(
+        "Simulator listening on tcp : {}",
+        listener.local_addr().map_err(|e| e.to_string())?
+    );
+
+    // 한 번에 한 client
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Accept failed : {}", e);
+                continue;
+            }
+        };
+        let mut transport = match TcpTransport::from_stream(stream) {
+            Ok(transport) => transport,
+            Err(e) => {
+                warn!("Client rejected : {}", e);
+                continue;
+            }
+        };
+
+        info!("Client connected : {}", transport.name());
+        if let Err(e) = simulator.run(&mut transport) {
+            info!("Client disconnected : {}", e);
+        }
+    }
+
+    Ok(())
+}
+
+fn init_logger() {
+    let stdout = ConsoleAppender::builder()
+        .encoder(Box::new(PatternEncoder::new(LOG_PATTERN)))
+        .target(log4rs::append::console::Target::Stderr)
+        .build();
+    let config = Config::builder()
+        .appender(Appender::builder().build("stderr", Box::new(stdout)))
+        .build(Root::builder().appender("stderr").build(LevelFilter::Info))
+        .unwrap();
+
+    log4rs::init_config(config).unwrap();
+}
+
+fn main() {
+    init_logger();
+
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let listen = args.listen.clone();
+    let mut simulator = Simulator::new(args);
+    let result = match listen {
+        Listen::Pty => run_pty(&mut simulator),
+        Listen::Tcp(address) => run_tcp(&mut simulator, &address),
+    };
+
+    if let Err(e) = result {
+        error!("{}", e);
+        process::exit(1);
+    }
+}
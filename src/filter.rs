@@ -0,0 +1,483 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::packet_log::LogEntry;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    /// hex 목록에서 읽을 수 없는 항목
+    InvalidItem(String),
+    /// 식에서 `position` 번째 글자 근처의 문법 오류
+    Syntax { position: usize, message: String },
+}
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::InvalidItem(item) => write!(f, "invalid item '{}'", item),
+            FilterError::Syntax { position, message } => {
+                write!(f, "{} at column {}", message, position + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for FilterError {}
+
+/// 바이트 하나에 대한 hex 목록 필터. `,` 나 공백으로 구분하고 `-` 는 범위, `!` 는 제외이다.
+/// 포함 항목이 없으면 제외 항목만 빼고 모두 통과한다
+///
+/// ```
+/// use RUST_tutorial::filter::ByteFilter;
+///
+/// let filter = ByteFilter::parse("C1, 10-1F, !15").unwrap();
+/// assert!(filter.matches(0xC1));
+/// assert!(filter.matches(0x10));
+/// assert!(!filter.matches(0x15));
+/// assert!(!filter.matches(0x20));
+/// assert!(ByteFilter::parse("!05").unwrap().matches(0x20));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ByteFilter {
+    include: Vec<RangeInclusive<u8>>,
+    exclude: Vec<RangeInclusive<u8>>,
+}
+
+impl ByteFilter {
+    pub fn parse(text: &str) -> Result<ByteFilter, FilterError> {
+        let mut filter = ByteFilter::default();
+
+        for item in text.split(|c: char| c == ',' || c.is_whitespace()) {
+            if item.is_empty() {
+                continue;
+            }
+            let invalid = || FilterError::InvalidItem(item.to_string());
+
+            let (negated, range) = match item.strip_prefix('!') {
+                Some(range) => (true, range),
+                None => (false, item),
+            };
+            let range = match range.split_once('-') {
+                Some((start, end)) => {
+                    let start = parse_hex(start).ok_or_else(invalid)?;
+                    let end = parse_hex(end).ok_or_else(invalid)?;
+                    if start > end {
+                        return Err(invalid());
+                    }
+                    start..=end
+                }
+                None => {
+                    let value = parse_hex(range).ok_or_else(invalid)?;
+                    value..=value
+                }
+            };
+
+            if negated {
+                filter.exclude.push(range);
+            } else {
+                filter.include.push(range);
+            }
+        }
+
+        Ok(filter)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn matches(&self, value: u8) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|range| range.contains(&value)))
+            && !self.exclude.iter().any(|range| range.contains(&value))
+    }
+}
+
+// `C1`, `0xC1` 모두 hex 로 읽는다
+fn parse_hex(text: &str) -> Option<u8> {
+    let text = text.trim();
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    u8::from_str_radix(digits, 16).ok()
+}
+
+/// 식에서 쓸 수 있는 패킷 값
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Value {
+    Number(u32),
+    Id,
+    Command,
+    Sequence,
+    Length,
+    Checksum,
+    /// DATA 바이트 수
+    DataLength,
+    Data(usize),
+}
+
+impl Value {
+    // SEQ 가 없는 형식이거나 DATA 범위를 벗어나면 None
+    fn eval(&self, entry: &LogEntry) -> Option<u32> {
+        match *self {
+            Value::Number(value) => Some(value),
+            Value::Id => Some(entry.id as u32),
+            Value::Command => Some(entry.command as u32),
+            Value::Sequence => entry.sequence.map(u32::from),
+            Value::Length => Some(entry.length as u32),
+            Value::Checksum => Some(entry.checksum as u32),
+            Value::DataLength => Some(entry.payload.len() as u32),
+            Value::Data(index) => entry.payload.get(index).map(|&byte| byte as u32),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Compare {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Compare {
+    fn apply(&self, left: u32, right: u32) -> bool {
+        match *self {
+            Compare::Eq => left == right,
+            Compare::Ne => left != right,
+            Compare::Lt => left < right,
+            Compare::Le => left <= right,
+            Compare::Gt => left > right,
+            Compare::Ge => left >= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare(Value, Compare, Value),
+}
+
+impl Node {
+    fn eval(&self, entry: &LogEntry) -> bool {
+        match self {
+            Node::And(left, right) => left.eval(entry) && right.eval(entry),
+            Node::Or(left, right) => left.eval(entry) || right.eval(entry),
+            Node::Not(node) => !node.eval(entry),
+            Node::Compare(left, compare, right) => match (left.eval(entry), right.eval(entry)) {
+                (Some(left), Some(right)) => compare.apply(left, right),
+                // 없는 값과의 비교는 항상 거짓
+                _ => false,
+            },
+        }
+    }
+}
+
+/// 패킷 필터 식.
+/// 값은 `id`, `cmd`, `seq`, `len`, `cs`, `data.len`, `data[n]` 이고 숫자는 10진수 또는 `0x` hex 이다.
+/// 비교는 `== != < <= > >=`, 조합은 `&& || !` 와 괄호를 쓴다.
+/// 없는 값 (DATA 범위 밖, SEQ 가 없는 형식) 과의 비교는 거짓이다
+///
+/// ```
+/// use RUST_tutorial::filter::Expression;
+///
+/// assert!(Expression::parse("id == 0xC1 && data[0] > 0x10 && len > 8").is_ok());
+/// assert!(Expression::parse("id == ").is_err());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    pub fn parse(text: &str) -> Result<Expression, FilterError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end: text.len(),
+        };
+        let root = parser.or()?;
+        if let Some((position, token)) = parser.tokens.get(parser.pos) {
+            return Err(FilterError::Syntax {
+                position: *position,
+                message: format!("unexpected {}", token),
+            });
+        }
+
+        Ok(Expression { root })
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.root.eval(entry)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Number(u32),
+    Ident(String),
+    Compare(Compare),
+    And,
+    Or,
+    Not,
+    Dot,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "number {}", value),
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Compare(_) => write!(f, "comparison"),
+            Token::And => write!(f, "'&&'"),
+            Token::Or => write!(f, "'||'"),
+            Token::Not => write!(f, "'!'"),
+            Token::Dot => write!(f, "'.'"),
+            Token::LParen => write!(f, "'('"),
+            Token::RParen => write!(f, "')'"),
+            Token::LBracket => write!(f, "'['"),
+            Token::RBracket => write!(f, "']'"),
+        }
+    }
+}
+
+// 토큰과 그 토큰이 시작하는 글자 위치
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, FilterError> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytes.len() {
+        // 붙여 넣은 식의 줄바꿈, NBSP 등도 공백으로 본다
+        if let Some(c) = text[pos..].chars().next().filter(|c| c.is_whitespace()) {
+            pos += c.len_utf8();
+            continue;
+        }
+
+        let start = pos;
+        let next = bytes.get(pos + 1).copied();
+        let (token, width) = match (bytes[pos], next) {
+            (b'=', Some(b'=')) => (Token::Compare(Compare::Eq), 2),
+            (b'!', Some(b'=')) => (Token::Compare(Compare::Ne), 2),
+            (b'<', Some(b'=')) => (Token::Compare(Compare::Le), 2),
+            (b'>', Some(b'=')) => (Token::Compare(Compare::Ge), 2),
+            (b'&', Some(b'&')) => (Token::And, 2),
+            (b'|', Some(b'|')) => (Token::Or, 2),
+            (b'<', _) => (Token::Compare(Compare::Lt), 1),
+            (b'>', _) => (Token::Compare(Compare::Gt), 1),
+            (b'!', _) => (Token::Not, 1),
+            (b'.', _) => (Token::Dot, 1),
+            (b'(', _) => (Token::LParen, 1),
+            (b')', _) => (Token::RParen, 1),
+            (b'[', _) => (Token::LBracket, 1),
+            (b']', _) => (Token::RBracket, 1),
+            (b'0'..=b'9', _) => {
+                while pos < bytes.len() && bytes[pos].is_ascii_alphanumeric() {
+                    pos += 1;
+                }
+                let word = &text[start..pos];
+                let value = match word.strip_prefix("0x").or(word.strip_prefix("0X")) {
+                    Some(hex) => u32::from_str_radix(hex, 16),
+                    None => word.parse(),
+                };
+                let value = value.map_err(|_| FilterError::Syntax {
+                    position: start,
+                    message: format!("invalid number '{}'", word),
+                })?;
+                tokens.push((start, Token::Number(value)));
+                continue;
+            }
+            (byte, _) if byte.is_ascii_alphabetic() || byte == b'_' => {
+                while pos < bytes.len()
+                    && (bytes[pos].is_ascii_alphanumeric() || bytes[pos] == b'_')
+                {
+                    pos += 1;
+                }
+                tokens.push((start, Token::Ident(text[start..pos].to_lowercase())));
+                continue;
+            }
+            _ => {
+                return Err(FilterError::Syntax {
+                    position: start,
+                    message: format!("unexpected '{}'", text[start..].chars().next().unwrap()),
+                });
+            }
+        };
+
+        pos += width;
+        tokens.push((start, token));
+    }
+
+    Ok(tokens)
+}
+
+// or := and ('||' and)* / and := not ('&&' not)* / not := '!' not | '(' or ')' | value cmp value
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(_, token)| token)
+    }
+
+    fn error(&self, message: &str) -> FilterError {
+        let position = self
+            .tokens
+            .get(self.pos)
+            .map_or(self.end, |(position, _)| *position);
+        FilterError::Syntax {
+            position,
+            message: message.to_string(),
+        }
+    }
+
+    fn expect(&mut self, token: Token) -> Result<(), FilterError> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {}", token)))
+        }
+    }
+
+    fn or(&mut self) -> Result<Node, FilterError> {
+        let mut node = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.pos += 1;
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, FilterError> {
+        let mut node = self.not()?;
+        while self.peek() == Some(&Token::And) {
+            self.pos += 1;
+            node = Node::And(Box::new(node), Box::new(self.not()?));
+        }
+        Ok(node)
+    }
+
+    fn not(&mut self) -> Result<Node, FilterError> {
+        match self.peek() {
+            Some(Token::Not) => {
+                self.pos += 1;
+                Ok(Node::Not(Box::new(self.not()?)))
+            }
+            Some(Token::LParen) => {
+                self.pos += 1;
+                let node = self.or()?;
+                self.expect(Token::RParen)?;
+                Ok(node)
+            }
+            _ => {
+                let left = self.value()?;
+                let compare = match self.peek() {
+                    Some(Token::Compare(compare)) => *compare,
+                    _ => return Err(self.error("expected comparison")),
+                };
+                self.pos += 1;
+                let right = self.value()?;
+                Ok(Node::Compare(left, compare, right))
+            }
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, FilterError> {
+        let Some(token) = self.peek().cloned() else {
+            return Err(self.error("expected value"));
+        };
+        let value = match token {
+            Token::Number(value) => Value::Number(value),
+            Token::Ident(name) => match name.as_str() {
+                "id" => Value::Id,
+                "cmd" => Value::Command,
+                "seq" => Value::Sequence,
+                "len" => Value::Length,
+                "cs" => Value::Checksum,
+                "data" => return self.data(),
+                _ => return Err(self.error(&format!("unknown value '{}'", name))),
+            },
+            _ => return Err(self.error("expected value")),
+        };
+        self.pos += 1;
+
+        Ok(value)
+    }
+
+    // `data[n]` 또는 `data.len`
+    fn data(&mut self) -> Result<Value, FilterError> {
+        self.pos += 1;
+        match self.peek() {
+            Some(Token::LBracket) => {
+                self.pos += 1;
+                let Some(&Token::Number(index)) = self.peek() else {
+                    return Err(self.error("expected index"));
+                };
+                self.pos += 1;
+                self.expect(Token::RBracket)?;
+                Ok(Value::Data(index as usize))
+            }
+            Some(Token::Dot) => {
+                self.pos += 1;
+                match self.peek() {
+                    Some(Token::Ident(name)) if name == "len" => {
+                        self.pos += 1;
+                        Ok(Value::DataLength)
+                    }
+                    _ => Err(self.error("expected 'len'")),
+                }
+            }
+            _ => Err(self.error("expected '[' or '.len'")),
+        }
+    }
+}
+
+/// 로그와 저장에 쓰는 필터. ID 목록, CMD 목록, 식을 모두 만족해야 통과한다
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PacketFilter {
+    pub id: ByteFilter,
+    pub command: ByteFilter,
+    pub expression: Option<Expression>,
+}
+
+impl PacketFilter {
+    /// 빈 문자열은 모두 통과시킨다
+    pub fn parse(id: &str, command: &str, expression: &str) -> Result<PacketFilter, FilterError> {
+        Ok(PacketFilter {
+            id: ByteFilter::parse(id)?,
+            command: ByteFilter::parse(command)?,
+            expression: if expression.trim().is_empty() {
+                None
+            } else {
+                Some(Expression::parse(expression)?)
+            },
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.id.is_empty() && self.command.is_empty() && self.expression.is_none()
+    }
+
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.id.matches(entry.id)
+            && self.command.matches(entry.command)
+            && self
+                .expression
+                .as_ref()
+                .map_or(true, |expression| expression.matches(entry))
+    }
+}
@@ -1,7 +1,15 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![allow(non_snake_case)]
 
-mod app;
-mod protocol;
-mod serial;
+pub mod app;
+pub mod filter;
+pub mod library;
+pub mod packet_log;
+pub mod protocol;
+pub mod rfc2217;
+pub mod scheduler;
+pub mod serial;
+pub mod session;
+pub mod share;
+pub mod transport;
 pub use app::SerialApp;
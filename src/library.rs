@@ -0,0 +1,246 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::protocol::{parse_hex, FrameSpec, PacketBuilder, PACKET};
+
+#[derive(Debug)]
+pub enum LibraryError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    TomlRead(toml::de::Error),
+    TomlWrite(toml::ser::Error),
+    /// 확장자가 .json, .toml 이 아니다
+    UnknownFormat(String),
+    /// 명령의 ID, CMD, payload 를 바이트로 만들 수 없다
+    Invalid(String),
+}
+
+impl fmt::Display for LibraryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LibraryError::Io(e) => write!(f, "cannot access library : {}", e),
+            LibraryError::Json(e) => write!(f, "invalid library json : {}", e),
+            LibraryError::TomlRead(e) => write!(f, "invalid library toml : {}", e),
+            LibraryError::TomlWrite(e) => write!(f, "cannot write library toml : {}", e),
+            LibraryError::UnknownFormat(path) => {
+                write!(f, "{} : use a .json or .toml file", path)
+            }
+            LibraryError::Invalid(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LibraryError {}
+
+/// 자주 보내는 프레임 하나. ID, CMD, payload 는 hex 문자열로 둔다.
+///
+/// payload 에는 보낼 때 값이 정해지는 자리표시자를 넣을 수 있다.
+/// - `{seq}` : 보낼 때의 SEQ
+/// - `{time}` : UNIX 시각(초), 4 바이트
+/// - `{u8:N}`, `{u16:N}`, `{u32:N}` : 10진수 또는 `0x` hex 값 N 을 big endian 으로
+///
+/// ```
+/// use RUST_tutorial::library::Command;
+///
+/// let command = Command {
+///     payload: String::from("01 {seq} {u16:0x1234}"),
+///     ..Default::default()
+/// };
+/// assert_eq!(command.expand_payload(0x7F).unwrap(), [0x01, 0x7F, 0x12, 0x34]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Command {
+    pub name: String,
+    pub group: String,
+    pub id: String,
+    pub command: String,
+    pub payload: String,
+    /// `Ctrl+1`, `F5` 처럼 적는다. 비어 있으면 단축키 없음
+    pub shortcut: String,
+}
+
+impl Command {
+    pub fn expand_payload(&self, sequence: u8) -> Result<Vec<u8>, LibraryError> {
+        let invalid = || LibraryError::Invalid(format!("{} : invalid payload", self.name));
+        let mut bytes = Vec::new();
+        let mut rest = self.payload.as_str();
+
+        while let Some(start) = rest.find('{') {
+            bytes.extend(parse_hex(&rest[..start]).ok_or_else(invalid)?);
+            let end = rest[start..].find('}').ok_or_else(invalid)? + start;
+            let placeholder = &rest[start + 1..end];
+            bytes.extend(expand_placeholder(placeholder, sequence).ok_or_else(|| {
+                LibraryError::Invalid(format!(
+                    "{} : unknown placeholder {{{}}}",
+                    self.name, placeholder
+                ))
+            })?);
+            rest = &rest[end + 1..];
+        }
+        bytes.extend(parse_hex(rest).ok_or_else(invalid)?);
+
+        Ok(bytes)
+    }
+
+    /// `spec` 에 맞는 패킷을 만든다. LEN, CS 는 계산한다
+    pub fn build(&self, spec: &FrameSpec, sequence: u8) -> Result<PACKET, LibraryError> {
+        let byte = |label: &str, text: &str| match parse_hex(text).as_deref() {
+            Some(&[value]) => Ok(value),
+            _ => Err(LibraryError::Invalid(format!(
+                "{} : {} : one hex byte expected",
+                self.name, label
+            ))),
+        };
+
+        PacketBuilder::new()
+            .spec(spec)
+            .id(byte("ID", &self.id)?)
+            .command(byte("CMD", &self.command)?)
+            .sequence(sequence)
+            .payload(&self.expand_payload(sequence)?)
+            .build()
+            .map_err(|e| LibraryError::Invalid(format!("{} : {}", self.name, e)))
+    }
+
+    /// 단축키를 egui 키 조합으로 바꾼다. 비어 있거나 읽을 수 없으면 None
+    pub fn keyboard_shortcut(&self) -> Option<egui::KeyboardShortcut> {
+        parse_shortcut(&self.shortcut)
+    }
+}
+
+fn expand_placeholder(placeholder: &str, sequence: u8) -> Option<Vec<u8>> {
+    let placeholder = placeholder.trim();
+    match placeholder {
+        "seq" => return Some(vec![sequence]),
+        "time" => {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).ok()?;
+            return Some((now.as_secs() as u32).to_be_bytes().to_vec());
+        }
+        _ => {}
+    }
+
+    let (kind, value) = placeholder.split_once(':')?;
+    let value = value.trim();
+    let value = match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+        None => value.parse().ok()?,
+    };
+    match kind.trim() {
+        "u8" => Some(vec![u8::try_from(value).ok()?]),
+        "u16" => Some(u16::try_from(value).ok()?.to_be_bytes().to_vec()),
+        "u32" => Some(value.to_be_bytes().to_vec()),
+        _ => None,
+    }
+}
+
+/// `Ctrl+Shift+1`, `Alt+F2`, `F5` 같은 문자열을 읽는다
+pub fn parse_shortcut(text: &str) -> Option<egui::KeyboardShortcut> {
+    let mut modifiers = egui::Modifiers::NONE;
+    let mut key = None;
+
+    for part in text.split('+').map(str::trim) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" => modifiers = modifiers | egui::Modifiers::CTRL,
+            "alt" => modifiers = modifiers | egui::Modifiers::ALT,
+            "shift" => modifiers = modifiers | egui::Modifiers::SHIFT,
+            "cmd" => modifiers = modifiers | egui::Modifiers::COMMAND,
+            _ if key.is_none() => key = Some(egui::Key::from_name(part)?),
+            _ => return None,
+        }
+    }
+
+    Some(egui::KeyboardShortcut::new(modifiers, key?))
+}
+
+/// 이름을 붙여 그룹으로 묶어 둔 명령 목록. 앱 상태와 함께 저장되고 파일로 주고받을 수 있다
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Library {
+    pub commands: Vec<Command>,
+}
+
+impl Library {
+    /// 처음 나온 순서대로 그룹 이름
+    pub fn groups(&self) -> Vec<&str> {
+        let mut groups: Vec<&str> = Vec::new();
+        for command in &self.commands {
+            if !groups.contains(&command.group.as_str()) {
+                groups.push(&command.group);
+            }
+        }
+        groups
+    }
+
+    /// 가져온 명령을 더한다. 같은 그룹에 같은 이름이 있으면 바꾼다
+    pub fn merge(&mut self, other: Library) {
+        for command in other.commands {
+            match self
+                .commands
+                .iter_mut()
+                .find(|old| old.group == command.group && old.name == command.name)
+            {
+                Some(old) => *old = command,
+                None => self.commands.push(command),
+            }
+        }
+    }
+
+    pub fn from_json(text: &str) -> Result<Library, LibraryError> {
+        serde_json::from_str(text).map_err(LibraryError::Json)
+    }
+
+    pub fn to_json(&self) -> Result<String, LibraryError> {
+        serde_json::to_string_pretty(self).map_err(LibraryError::Json)
+    }
+
+    pub fn from_toml(text: &str) -> Result<Library, LibraryError> {
+        toml::from_str(text).map_err(LibraryError::TomlRead)
+    }
+
+    pub fn to_toml(&self) -> Result<String, LibraryError> {
+        toml::to_string_pretty(self).map_err(LibraryError::TomlWrite)
+    }
+
+    /// 확장자(.json, .toml)로 형식을 정해서 읽는다
+    pub fn load(path: impl AsRef<Path>) -> Result<Library, LibraryError> {
+        let path = path.as_ref();
+        let format = Format::of(path)?;
+        let text = fs::read_to_string(path).map_err(LibraryError::Io)?;
+        match format {
+            Format::Json => Library::from_json(&text),
+            Format::Toml => Library::from_toml(&text),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), LibraryError> {
+        let path = path.as_ref();
+        let text = match Format::of(path)? {
+            Format::Json => self.to_json()?,
+            Format::Toml => self.to_toml()?,
+        };
+        fs::write(path, text).map_err(LibraryError::Io)
+    }
+}
+
+enum Format {
+    Json,
+    Toml,
+}
+
+impl Format {
+    fn of(path: &Path) -> Result<Format, LibraryError> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("json") => Ok(Format::Json),
+            Some(ext) if ext.eq_ignore_ascii_case("toml") => Ok(Format::Toml),
+            _ => Err(LibraryError::UnknownFormat(path.display().to_string())),
+        }
+    }
+}
@@ -5,14 +5,8 @@ use log4rs::{
     encode::pattern::PatternEncoder,
     Handle,
 };
-use std::{
-    sync::{Arc, Mutex},
-    thread,
-};
 
-mod app;
-mod protocol;
-mod serial;
+use RUST_tutorial::{app, serial};
 
 // const LOG_PATTERN: &str = "[{d} {l}] {m}{n}";
 const LOG_PATTERN: &str = "[{d(%Y-%m-%d %H:%M:%S%.3f)} {l}] {m}{n}";
@@ -75,35 +69,6 @@ fn change_log_level(handle: &Handle, new_level: LevelFilter) {
     handle.set_config(new_config);
 }
 
-fn spawn_read_thread(serial: Arc<Mutex<serial::SERIAL>>) -> thread::JoinHandle<()> {
-    thread::spawn(move || {
-        let mut packet: protocol::PACKET = protocol::PACKET::new();
-
-        loop {
-            let d = {
-                let mut serial = serial.lock().unwrap();
-                serial.read()
-            };
-            trace!("Serial receive : {:02X} ", d);
-            let (rslt, p) = packet.parse(d);
-            if rslt {
-                // trace!("Valid PACKET\r\n{}", p.to_string());
-                debug!("Packet Received\r\n{}", p.to_string());
-            }
-        }
-    })
-}
-
-fn spawn_write_thread(serial: Arc<Mutex<serial::SERIAL>>) -> thread::JoinHandle<()> {
-    thread::spawn(move || loop {
-        {
-            let mut serial = serial.lock().unwrap();
-            serial.write(b'a');
-        }
-        thread::sleep(std::time::Duration::from_millis(1000));
-    })
-}
-
 fn main() -> eframe::Result {
     let log_handle = init_logger();
     // change_log_level(&log_handle, LevelFilter::Trace);
@@ -121,12 +86,7 @@ fn main() -> eframe::Result {
        // let write_thread = spawn_write_thread(serial);
     */
 
-    let serial = Arc::new(Mutex::new(serial::SERIAL::new()));
-    serial.lock().unwrap().scan_ports();
-    serial.lock().unwrap().init(&String::from("COM3"), 9_600);
-
-    let read_thread = spawn_read_thread(Arc::clone(&serial));
-    let write_thread = spawn_write_thread(Arc::clone(&serial));
+    serial::SERIAL::scan_ports();
 
     // EGUI START
     let native_options = eframe::NativeOptions {
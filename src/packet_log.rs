@@ -0,0 +1,344 @@
+use chrono::{DateTime, Local};
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use crate::filter::PacketFilter;
+use crate::protocol::{Field, FrameSpec, ParseError, PACKET};
+
+/// 로그에 남기는 최대 행 수. 넘으면 오래된 행부터 지운다
+pub const MAX_LOG_ROWS: usize = 1_000_000;
+
+/// 송수신 방향
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Rx,
+    Tx,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Direction::Rx => write!(f, "RX"),
+            Direction::Tx => write!(f, "TX"),
+        }
+    }
+}
+
+/// CS 검사 결과
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumStatus {
+    Ok,
+    Mismatch { expected: u16, got: u16 },
+}
+
+impl fmt::Display for ChecksumStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ChecksumStatus::Ok => write!(f, "OK"),
+            ChecksumStatus::Mismatch { expected, .. } => write!(f, "BAD ({:02X})", expected),
+        }
+    }
+}
+
+/// 패킷 로그의 한 행
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub direction: Direction,
+    pub id: u8,
+    pub command: u8,
+    /// SEQ 가 없는 형식이면 None
+    pub sequence: Option<u8>,
+    pub length: u16,
+    pub payload: Vec<u8>,
+    pub checksum: u16,
+    pub status: ChecksumStatus,
+    /// 패킷이 아닌 표시 행 (재연결 등) 의 내용. 있으면 나머지 필드는 의미가 없다
+    pub note: Option<String>,
+    // 매 프레임 다시 만들지 않도록 미리 만들어 둔 DATA hex
+    payload_hex: String,
+}
+
+impl LogEntry {
+    /// 보낸 패킷은 CS 를 일부러 틀리게 보냈을 수 있으므로 다시 계산해서 상태를 정한다
+    pub fn from_packet(direction: Direction, packet: &PACKET) -> LogEntry {
+        let expected = packet.expected_cs();
+        let status = if expected == packet.checksum {
+            ChecksumStatus::Ok
+        } else {
+            ChecksumStatus::Mismatch {
+                expected,
+                got: packet.checksum,
+            }
+        };
+
+        LogEntry::new(direction, packet, status)
+    }
+
+    /// CS 가 맞지 않는 프레임만 행으로 만든다. 나머지 오류는 프레임이라고 볼 수 없다
+    pub fn from_error(
+        direction: Direction,
+        spec: &FrameSpec,
+        error: &ParseError,
+    ) -> Option<LogEntry> {
+        let ParseError::ChecksumMismatch { expected, got, raw } = error else {
+            return None;
+        };
+        let broken = PACKET::from_raw(spec, raw)?;
+
+        Some(LogEntry::new(
+            direction,
+            &broken,
+            ChecksumStatus::Mismatch {
+                expected: *expected,
+                got: *got,
+            },
+        ))
+    }
+
+    fn new(direction: Direction, packet: &PACKET, status: ChecksumStatus) -> LogEntry {
+        let mut payload_hex = String::with_capacity(packet.payload().len() * 3);
+        for (i, byte) in packet.payload().iter().enumerate() {
+            if i > 0 {
+                payload_hex.push(' ');
+            }
+            write!(payload_hex, "{:02X}", byte).unwrap();
+        }
+
+        LogEntry {
+            time: Local::now(),
+            direction,
+            id: packet.header.id,
+            command: packet.header.command,
+            sequence: packet
+                .spec()
+                .fields
+                .contains(&Field::Sequence)
+                .then_some(packet.header.sequence),
+            length: packet.header.length,
+            payload: packet.payload().to_vec(),
+            checksum: packet.checksum,
+            status,
+            note: None,
+            payload_hex,
+        }
+    }
+
+    /// 연결이 끊기고 다시 붙은 자리처럼 로그 흐름에 남길 표시 행
+    pub fn marker(note: impl Into<String>) -> LogEntry {
+        LogEntry {
+            time: Local::now(),
+            direction: Direction::Rx,
+            id: 0,
+            command: 0,
+            sequence: None,
+            length: 0,
+            payload: Vec::new(),
+            checksum: 0,
+            status: ChecksumStatus::Ok,
+            note: Some(note.into()),
+            payload_hex: String::new(),
+        }
+    }
+
+    pub fn payload_hex(&self) -> &str {
+        &self.payload_hex
+    }
+}
+
+/// 화면에 보여줄 패킷 로그. 일시정지 중에 들어온 행은 따로 모아 두었다가 재개할 때 붙인다.
+/// filter 를 통과한 행 목록을 따로 유지해서 행이 많아도 필터링된 화면을 바로 그릴 수 있다
+#[derive(Debug)]
+pub struct PacketLog {
+    entries: VecDeque<LogEntry>,
+    // entries[0] 의 통산 번호 (앞에서 지운 행 수)
+    first: u64,
+    // filter 를 통과한 행의 통산 번호
+    visible: VecDeque<u64>,
+    filter: PacketFilter,
+    held: VecDeque<LogEntry>,
+    paused: bool,
+    capacity: usize,
+}
+
+impl Default for PacketLog {
+    fn default() -> Self {
+        PacketLog::new()
+    }
+}
+
+impl PacketLog {
+    pub fn new() -> PacketLog {
+        PacketLog::with_capacity(MAX_LOG_ROWS)
+    }
+
+    pub fn with_capacity(capacity: usize) -> PacketLog {
+        PacketLog {
+            entries: VecDeque::new(),
+            first: 0,
+            visible: VecDeque::new(),
+            filter: PacketFilter::default(),
+            held: VecDeque::new(),
+            paused: false,
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, entry: LogEntry) {
+        if self.paused {
+            if self.held.len() >= self.capacity {
+                self.held.pop_front();
+            }
+            self.held.push_back(entry);
+        } else {
+            self.append(entry);
+        }
+    }
+
+    fn append(&mut self, entry: LogEntry) {
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+            if self.visible.front() == Some(&self.first) {
+                self.visible.pop_front();
+            }
+            self.first += 1;
+        }
+        if self.shows(&entry) {
+            self.visible
+                .push_back(self.first + self.entries.len() as u64);
+        }
+        self.entries.push_back(entry);
+    }
+
+    // 표시 행은 filter 와 상관없이 보여서 끊긴 구간을 알 수 있게 한다
+    fn shows(&self, entry: &LogEntry) -> bool {
+        entry.note.is_some() || self.filter.matches(entry)
+    }
+
+    pub fn filter(&self) -> &PacketFilter {
+        &self.filter
+    }
+
+    /// filter 를 바꾸면 이미 받은 행에도 바로 적용한다
+    pub fn set_filter(&mut self, filter: PacketFilter) {
+        self.filter = filter;
+        self.visible = self
+            .entries
+            .iter()
+            .zip(self.first..)
+            .filter(|(entry, _)| self.shows(entry))
+            .map(|(_, number)| number)
+            .collect();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        if !paused {
+            let held = std::mem::take(&mut self.held);
+            for entry in held {
+                self.append(entry);
+            }
+        }
+    }
+
+    /// 일시정지 중에 들어와서 아직 보이지 않는 행 수
+    pub fn held(&self) -> usize {
+        self.held.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&LogEntry> {
+        self.entries.get(index)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    /// filter 를 통과한 행 수
+    pub fn visible_len(&self) -> usize {
+        self.visible.len()
+    }
+
+    /// filter 를 통과한 행 중 `index` 번째
+    pub fn visible(&self, index: usize) -> Option<&LogEntry> {
+        let number = *self.visible.get(index)?;
+        self.entries.get((number - self.first) as usize)
+    }
+
+    pub fn iter_visible(&self) -> impl Iterator<Item = &LogEntry> {
+        self.visible
+            .iter()
+            .filter_map(|&number| self.entries.get((number - self.first) as usize))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.visible.clear();
+        self.first = 0;
+        self.held.clear();
+    }
+
+    /// filter 를 통과한 행을 CSV 로 저장하고 저장한 행 수를 돌려준다
+    pub fn save_csv(&self, path: impl AsRef<Path>) -> io::Result<usize> {
+        let mut out = BufWriter::new(File::create(path)?);
+        writeln!(out, "time,dir,id,cmd,seq,len,data,cs,status")?;
+
+        let mut count = 0;
+        for entry in self.iter_visible() {
+            count += 1;
+            // 표시 행은 status 칸에 내용을 쓴다
+            if let Some(note) = &entry.note {
+                writeln!(
+                    out,
+                    "{},,,,,,,,{}",
+                    entry.time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                    csv_field(note)
+                )?;
+                continue;
+            }
+            writeln!(
+                out,
+                "{},{},{:02X},{:02X},{},{:02X},{},{:02X},{}",
+                entry.time.format("%Y-%m-%d %H:%M:%S%.3f"),
+                entry.direction,
+                entry.id,
+                entry.command,
+                entry
+                    .sequence
+                    .map_or(String::new(), |sequence| format!("{:02X}", sequence)),
+                entry.length,
+                entry.payload_hex,
+                entry.checksum,
+                csv_field(&entry.status.to_string()),
+            )?;
+        }
+        out.flush()?;
+
+        Ok(count)
+    }
+}
+
+// RFC 4180. `,` `"` 줄바꿈이 있으면 `"` 로 감싸고 안의 `"` 는 두 번 쓴다
+fn csv_field(text: &str) -> Cow<'_, str> {
+    if text.contains([',', '"', '\r', '\n']) {
+        Cow::Owned(format!("\"{}\"", text.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(text)
+    }
+}
@@ -8,10 +8,21 @@ use log4rs::{
 };
 use prettytable::{row, Cell, Row, Table};
 use serde::{Deserialize, Serialize};
-use std::fmt::Write;
+use std::collections::VecDeque;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use strum_macros::EnumIter;
 
 const STX: u8 = 0x02;
 
+// 기본 프레임의 헤더 : STX, ID, LEN, CMD, SEQ 5바이트. CS 와 함께 LEN 에 포함된다
+const OVERHEAD: u8 = 5 + 1;
+/// 기본 (1바이트 CS) 프레임에 실을 수 있는 최대 DATA 길이
+pub const MAX_PAYLOAD: usize = (u8::MAX - OVERHEAD) as usize;
+
 const TYPE_STX: u8 = 0x00;
 const TYPE_ID: u8 = 0x01;
 const TYPE_LENGTH: u8 = 0x02;
@@ -21,63 +32,150 @@ const TYPE_DATA: u8 = 0x05;
 const TYPE_CHECKSUM: u8 = 0x06;
 
 const STEP_STX: u8 = 0x00;
-const STEP_ID: u8 = 0x01;
-const STEP_LENGTH: u8 = 0x02;
-const STEP_COMMAND: u8 = 0x03;
-const STEP_SEQUENCE: u8 = 0x04;
+const STEP_HEADER: u8 = 0x01;
 const STEP_DATA: u8 = 0x05;
+const STEP_CHECKSUM: u8 = 0x06;
 
 #[derive(Debug, Clone, Copy)]
 pub struct HEADER {
+    /// sync 의 첫 바이트
     pub stx: u8,
     pub id: u8,
-    pub length: u8,
+    pub length: u16,
     pub command: u8,
     pub sequence: u8,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct PACKET {
     pub header: HEADER,
-    pub data: [u8; 256],
-    pub checksum: u8,
+    pub data: Vec<u8>,
+    pub checksum: u16,
 
+    spec: FrameSpec,
     step: u8,
-    len_check: u8,
+    sync_check: u8,
+    field_check: u8,
+    byte_check: u8,
+    len_check: usize,
+    cs_check: u8,
+    received: usize,
+    discarded: usize,
+}
+
+impl Default for PACKET {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl PACKET {
     pub fn new() -> PACKET {
+        PACKET::with_spec(FrameSpec::default())
+    }
+
+    /// `spec` 형식으로 조립/직렬화 하는 패킷
+    pub fn with_spec(spec: FrameSpec) -> PACKET {
         PACKET {
             header: HEADER {
-                stx: STX,
+                stx: spec.sync.first().copied().unwrap_or(STX),
                 id: 0x00,
                 length: 0x00,
                 command: 0x00,
                 sequence: 0x00,
             },
-            data: [0; 256],
+            data: Vec::new(),
             checksum: 0x00,
-            step: 0x00,
+            spec,
+            step: STEP_STX,
+            sync_check: 0x00,
+            field_check: 0x00,
+            byte_check: 0x00,
             len_check: 0x00,
+            cs_check: 0x00,
+            received: 0,
+            discarded: 0,
         }
     }
 
-    fn serialize(&self) -> Vec<u8> {
+    /// sync 부터 CS 까지의 `raw` 를 `spec` 대로 나눠서 필드를 채운다. CS 는 검사하지 않는다.
+    /// CS 가 맞지 않아 버려진 프레임 (`ParseError::ChecksumMismatch`) 을 보여줄 때 쓴다
+    pub fn from_raw(spec: &FrameSpec, raw: &[u8]) -> Option<PACKET> {
+        let mut packet = PACKET::with_spec(spec.clone());
+        let mut rest = raw.strip_prefix(spec.sync.as_slice())?;
+
+        for field in &spec.fields {
+            let (t, width) = match field {
+                Field::Id => (TYPE_ID, 1),
+                Field::Length => (TYPE_LENGTH, spec.length_width as usize),
+                Field::Command => (TYPE_COMMAND, 1),
+                Field::Sequence => (TYPE_SEQUENCE, 1),
+            };
+            if rest.len() < width {
+                return None;
+            }
+            let (bytes, tail) = rest.split_at(width);
+            for &byte in bytes {
+                packet.update(t, byte);
+            }
+            rest = tail;
+        }
+
+        let data_len = rest.len().checked_sub(spec.checksum.width())?;
+        let (data, checksum) = rest.split_at(data_len);
+        packet.data = data.to_vec();
+        for &byte in checksum {
+            packet.update(TYPE_CHECKSUM, byte);
+            packet.cs_check += 1;
+        }
+        packet.cs_check = 0;
+
+        Some(packet)
+    }
+
+    pub fn spec(&self) -> &FrameSpec {
+        &self.spec
+    }
+
+    /// LEN 으로 계산한 DATA 바이트 수. LEN 이 헤더 + CS 보다 작으면 0
+    pub fn payload_len(&self) -> usize {
+        if self.spec.length_includes_overhead {
+            (self.header.length as usize).saturating_sub(self.spec.overhead())
+        } else {
+            self.header.length as usize
+        }
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.data
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
         let mut packet: Vec<u8> = Vec::new();
-        packet.push(self.header.stx);
-        packet.push(self.header.id);
-        packet.push(self.header.length);
-        packet.push(self.header.command);
-        packet.push(self.header.sequence);
+        packet.extend(&self.spec.sync);
 
-        for i in 0..(self.header.length - 6) {
-            packet.push(self.data[i as usize]);
+        for field in &self.spec.fields {
+            match field {
+                Field::Id => packet.push(self.header.id),
+                Field::Length => {
+                    let len = self.header.length.to_be_bytes();
+                    let width = self.spec.length_width.clamp(1, 2) as usize;
+                    packet.extend(&len[2 - width..]);
+                }
+                Field::Command => packet.push(self.header.command),
+                Field::Sequence => packet.push(self.header.sequence),
+            }
         }
 
-        packet.push(self.checksum);
+        packet.extend(self.payload());
+        packet.extend(self.spec.checksum.encode(self.checksum));
 
-        return packet;
+        packet
+    }
+
+    /// `serialize` 결과에 spec 의 framing (escape) 을 적용한 실제 송신 바이트
+    pub fn to_wire(&self) -> Vec<u8> {
+        self.spec.framing.encode(&self.serialize())
     }
 
     pub fn to_string(&self) -> String {
@@ -88,132 +186,236 @@ impl PACKET {
 
         // Build header row with centered alignment
         header_row.push(Cell::new("STX").style_spec("c"));
-        header_row.push(Cell::new("ID").style_spec("c"));
-        header_row.push(Cell::new("LEN").style_spec("c"));
-        header_row.push(Cell::new("CMD").style_spec("c"));
-        header_row.push(Cell::new("SEQ").style_spec("c"));
+        for field in &self.spec.fields {
+            header_row.push(Cell::new(field.label()).style_spec("c"));
+        }
 
-        for i in 0..(self.header.length - 6) {
+        // LEN 이 아니라 실제로 가진 DATA 기준. 손으로 만든 패킷은 둘이 다를 수 있다
+        for i in 0..self.payload().len() {
             header_row.push(Cell::new(&format!("D{}", i + 1)).style_spec("c"));
         }
 
         header_row.push(Cell::new("CS").style_spec("c"));
 
         // Build content row with centered alignment
-        content_row.push(Cell::new(&format!("{:02X}", self.header.stx)).style_spec("c"));
-        content_row.push(Cell::new(&format!("{:02X}", self.header.id)).style_spec("c"));
-        content_row.push(Cell::new(&format!("{:02X}", self.header.length)).style_spec("c"));
-        content_row.push(Cell::new(&format!("{:02X}", self.header.command)).style_spec("c"));
-        content_row.push(Cell::new(&format!("{:02X}", self.header.sequence)).style_spec("c"));
+        let sync_str: String = self
+            .spec
+            .sync
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect();
+        content_row.push(Cell::new(&sync_str).style_spec("c"));
+        for field in &self.spec.fields {
+            let value = match field {
+                Field::Id => format!("{:02X}", self.header.id),
+                Field::Length if self.spec.length_width == 2 => {
+                    format!("{:04X}", self.header.length)
+                }
+                Field::Length => format!("{:02X}", self.header.length),
+                Field::Command => format!("{:02X}", self.header.command),
+                Field::Sequence => format!("{:02X}", self.header.sequence),
+            };
+            content_row.push(Cell::new(&value).style_spec("c"));
+        }
 
-        for i in 0..(self.header.length - 6) {
-            content_row.push(Cell::new(&format!("{:02X}", self.data[i as usize])).style_spec("c"));
+        for byte in self.payload() {
+            content_row.push(Cell::new(&format!("{:02X}", byte)).style_spec("c"));
         }
 
-        content_row.push(Cell::new(&format!("{:02X}", self.checksum)).style_spec("c"));
+        let cs_str = match self.spec.checksum.width() {
+            1 => format!("{:02X}", self.checksum),
+            _ => format!("{:04X}", self.checksum),
+        };
+        content_row.push(Cell::new(&cs_str).style_spec("c"));
 
         table.add_row(Row::new(header_row));
         table.add_row(Row::new(content_row));
         write!(out_str, "{}", table).unwrap();
 
-        return out_str;
+        out_str
     }
 
-    fn check_cs(&self) -> bool {
+    /// 지금의 헤더와 DATA 로 계산한 CS. `checksum` 과 다르면 깨진 프레임이다
+    pub fn expected_cs(&self) -> u16 {
         // serialize 데이터를 가져옴
         let packet = self.serialize();
 
         // checksum 계산
-        let mut calc_cs: u8 = STX;
-        for i in 1..(packet.len() - 1) {
-            calc_cs ^= packet[i];
-            calc_cs = calc_cs.wrapping_add(1);
-        }
+        let cs_start = packet.len() - self.spec.checksum.width();
+        let calc_cs = self.spec.checksum.compute(&packet[..cs_start]);
 
-        // checksum 비교
         trace!(
-            "Checksum calc result : calc {:02X}, got {:02X}",
+            "Checksum calc result : calc {:04X}, got {:04X}",
             calc_cs,
             self.checksum
         );
 
-        if calc_cs == self.checksum {
-            return true;
-        } else {
-            return false;
-        }
+        calc_cs
     }
 
     fn clear(&mut self) {
-        self.header.stx = STX;
+        self.header.stx = self.spec.sync.first().copied().unwrap_or(STX);
         self.header.id = 0x00;
         self.header.length = 0x00;
         self.header.command = 0x00;
         self.header.sequence = 0x00;
-        self.data = [0; 256];
+        self.data = Vec::new();
         self.checksum = 0x00;
 
         self.step = STEP_STX;
+        self.sync_check = 0x00;
+        self.field_check = 0x00;
+        self.byte_check = 0x00;
         self.len_check = 0x00;
+        self.cs_check = 0x00;
+        self.received = 0;
     }
 
     fn update(&mut self, t: u8, value: u8) {
         match t {
             TYPE_STX => self.header.stx = value,
             TYPE_ID => self.header.id = value,
-            TYPE_LENGTH => self.header.length = value,
+            TYPE_LENGTH => {
+                // 2바이트 LEN 은 상위 바이트가 먼저 온다
+                self.header.length = (self.header.length << 8) | value as u16;
+            }
             TYPE_COMMAND => self.header.command = value,
             TYPE_SEQUENCE => self.header.sequence = value,
             TYPE_DATA => {
-                self.data[self.len_check as usize] = value;
+                self.data.push(value);
+            }
+            TYPE_CHECKSUM => {
+                // 2바이트 CS 는 하위 바이트가 먼저 온다
+                self.checksum |= (value as u16) << (8 * self.cs_check);
             }
-            TYPE_CHECKSUM => self.checksum = value,
             _ => trace!("Invalid type"),
         }
     }
 
-    pub fn parse(&mut self, value: u8) -> (bool, PACKET) {
-        let mut ret_packet: PACKET = PACKET::new();
-        let mut parse_rslt: bool = false;
+    /// STX 를 기다리는 중인지 (조립 중인 프레임이 없는지)
+    pub fn is_idle(&self) -> bool {
+        self.received == 0
+    }
+
+    /// 지금 조립 중인 프레임에 들어간 바이트 수 (sync 포함)
+    pub fn received(&self) -> usize {
+        self.received
+    }
+
+    // 헤더가 끝나면 DATA 길이를 확인하고 다음 단계로 넘어간다
+    fn finish_header(&mut self) -> ParseEvent {
+        let overhead = self.spec.overhead();
+        if self.spec.length_includes_overhead && (self.header.length as usize) < overhead {
+            // LEN 은 헤더와 CS 를 포함하므로 그보다 작을 수 없다
+            let length = self.header.length;
+            self.clear();
+            return ParseEvent::Error(ParseError::InvalidLength(length));
+        }
+
+        self.len_check = 0;
+        self.data = Vec::with_capacity(self.payload_len());
+        self.step = if self.payload_len() == 0 {
+            STEP_CHECKSUM
+        } else {
+            STEP_DATA
+        };
+
+        ParseEvent::Incomplete
+    }
+
+    /// 1 바이트씩 입력받아 패킷을 조립한다.
+    /// 프레임이 완성되지 않았으면 `ParseEvent::Incomplete` 를 돌려준다.
+    pub fn parse(&mut self, value: u8) -> ParseEvent {
+        let mut event = ParseEvent::Incomplete;
+        self.received += 1;
 
         match self.step {
+            // validate 하지 않은 spec 이면 sync 가 비어 있을 수 있다. 프레임 시작을 알 수 없으므로 버린다
+            STEP_STX if self.spec.sync.is_empty() => {
+                self.received = 0;
+                event = ParseEvent::Error(ParseError::DiscardedBytes(1));
+            }
             STEP_STX => {
-                if value == STX {
-                    self.update(TYPE_STX, value);
-                    self.step = STEP_ID;
+                if value == self.spec.sync[self.sync_check as usize] {
+                    if self.sync_check == 0 {
+                        self.update(TYPE_STX, value);
+                    }
+                    self.sync_check += 1;
+                } else {
+                    // sync 중간에 어긋나면 지금까지 받은 sync 바이트도 버린다
+                    self.discarded += self.sync_check as usize;
+                    self.sync_check = 0;
+                    if value == self.spec.sync[0] {
+                        self.update(TYPE_STX, value);
+                        self.sync_check = 1;
+                    } else {
+                        self.discarded += 1;
+                    }
+                }
+                self.received = self.sync_check as usize;
+
+                if self.sync_check as usize == self.spec.sync.len() {
+                    self.field_check = 0;
+                    self.byte_check = 0;
+                    self.step = STEP_HEADER;
+
+                    if self.discarded > 0 {
+                        event = ParseEvent::Error(ParseError::DiscardedBytes(self.discarded));
+                        self.discarded = 0;
+                    }
+
+                    if self.spec.fields.is_empty() {
+                        event = self.finish_header();
+                    }
                 }
             }
-            STEP_ID => {
-                self.update(TYPE_ID, value);
-                self.step = STEP_LENGTH;
-            }
-            STEP_LENGTH => {
-                self.update(TYPE_LENGTH, value);
-                self.len_check = 0x00;
-                self.step = STEP_COMMAND;
-            }
-            STEP_COMMAND => {
-                self.update(TYPE_COMMAND, value);
-                self.step = STEP_SEQUENCE;
-            }
-            STEP_SEQUENCE => {
-                self.update(TYPE_SEQUENCE, value);
-                self.step = STEP_DATA;
+            STEP_HEADER => {
+                let field = self.spec.fields[self.field_check as usize];
+                let width = match field {
+                    Field::Length => self.spec.length_width,
+                    _ => 1,
+                };
+
+                match field {
+                    Field::Id => self.update(TYPE_ID, value),
+                    Field::Length => self.update(TYPE_LENGTH, value),
+                    Field::Command => self.update(TYPE_COMMAND, value),
+                    Field::Sequence => self.update(TYPE_SEQUENCE, value),
+                }
+
+                self.byte_check += 1;
+                if self.byte_check >= width {
+                    self.byte_check = 0;
+                    self.field_check += 1;
+                    if self.field_check as usize >= self.spec.fields.len() {
+                        event = self.finish_header();
+                    }
+                }
             }
             STEP_DATA => {
-                if self.len_check >= (self.header.length - 6) {
-                    self.update(TYPE_CHECKSUM, value);
-                    if self.check_cs() {
-                        ret_packet = self.clone();
-                        parse_rslt = true;
+                self.update(TYPE_DATA, value);
+                self.len_check += 1;
+                if self.len_check >= self.payload_len() {
+                    self.step = STEP_CHECKSUM;
+                }
+            }
+            STEP_CHECKSUM => {
+                self.update(TYPE_CHECKSUM, value);
+                self.cs_check += 1;
+
+                if self.cs_check as usize >= self.spec.checksum.width() {
+                    let expected = self.expected_cs();
+                    if expected == self.checksum {
+                        event = ParseEvent::Frame(self.clone());
                     } else {
-                        trace!("Checksum Fail");
+                        event = ParseEvent::Error(ParseError::ChecksumMismatch {
+                            expected,
+                            got: self.checksum,
+                            raw: self.serialize(),
+                        });
                     }
 
                     self.clear();
-                } else {
-                    self.update(TYPE_DATA, value);
-                    self.len_check += 1;
                 }
             }
             _ => {
@@ -221,6 +423,981 @@ impl PACKET {
             }
         }
 
-        (parse_rslt, ret_packet)
+        event
+    }
+}
+
+/// 헤더 필드 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Field {
+    Id,
+    Length,
+    Command,
+    Sequence,
+}
+
+impl Field {
+    fn label(&self) -> &'static str {
+        match *self {
+            Field::Id => "ID",
+            Field::Length => "LEN",
+            Field::Command => "CMD",
+            Field::Sequence => "SEQ",
+        }
+    }
+}
+
+/// 프레임 형식 정의. 기본값은 `STX(02) ID LEN CMD SEQ DATA.. CS` 이고
+/// LEN 은 헤더와 CS 를 포함한 전체 길이이다.
+///
+/// TOML 로 다른 장치 형식을 정의할 수 있다.
+///
+/// ```toml
+/// name = "AA55 family"
+/// sync = [0xAA, 0x55]
+/// fields = ["Id", "Length", "Command"]
+/// length_width = 2
+/// length_includes_overhead = false
+/// checksum = "Crc16Modbus"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FrameSpec {
+    pub name: String,
+    /// 프레임 시작 바이트 (STX 또는 sync word)
+    pub sync: Vec<u8>,
+    /// sync 다음에 오는 헤더 필드 순서
+    pub fields: Vec<Field>,
+    /// LEN 필드 바이트 수 (1 또는 2, 2바이트는 상위 바이트가 먼저)
+    pub length_width: u8,
+    /// LEN 이 sync, 헤더, CS 까지 포함한 길이인지 (아니면 DATA 길이)
+    pub length_includes_overhead: bool,
+    pub checksum: ChecksumKind,
+    /// 선로에 보낼 때 프레임을 감싸는 방식 (STX 가 DATA 안에 나와도 안전하게)
+    pub framing: Framing,
+}
+
+impl Default for FrameSpec {
+    fn default() -> Self {
+        FrameSpec {
+            name: String::from("STX/ID/LEN/CMD/SEQ"),
+            sync: vec![STX],
+            fields: vec![Field::Id, Field::Length, Field::Command, Field::Sequence],
+            length_width: 1,
+            length_includes_overhead: true,
+            checksum: ChecksumKind::default(),
+            framing: Framing::None,
+        }
+    }
+}
+
+impl FrameSpec {
+    /// 기본 형식에서 LEN 만 2바이트로 늘린 형식. DATA 를 65000 바이트 넘게 실을 수 있다
+    pub fn extended() -> FrameSpec {
+        FrameSpec {
+            name: String::from("STX/ID/LEN16/CMD/SEQ"),
+            length_width: 2,
+            ..FrameSpec::default()
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<FrameSpec, FrameSpecError> {
+        let spec: FrameSpec = toml::from_str(text).map_err(FrameSpecError::Toml)?;
+        spec.validate()?;
+
+        Ok(spec)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<FrameSpec, FrameSpecError> {
+        let text = fs::read_to_string(path).map_err(FrameSpecError::Io)?;
+        FrameSpec::from_toml(&text)
+    }
+
+    pub fn validate(&self) -> Result<(), FrameSpecError> {
+        if self.sync.is_empty() {
+            return Err(FrameSpecError::Invalid(String::from("sync is empty")));
+        }
+        if !(1..=2).contains(&self.length_width) {
+            return Err(FrameSpecError::Invalid(format!(
+                "length_width must be 1 or 2, got {}",
+                self.length_width
+            )));
+        }
+        for (i, field) in self.fields.iter().enumerate() {
+            if self.fields[..i].contains(field) {
+                return Err(FrameSpecError::Invalid(format!(
+                    "{} appears more than once",
+                    field.label()
+                )));
+            }
+        }
+        if !self.fields.contains(&Field::Length) {
+            return Err(FrameSpecError::Invalid(String::from(
+                "LEN field is missing",
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// sync + 헤더 + CS 바이트 수
+    pub fn overhead(&self) -> usize {
+        let header: usize = self
+            .fields
+            .iter()
+            .map(|field| match field {
+                Field::Length => self.length_width as usize,
+                _ => 1,
+            })
+            .sum();
+
+        self.sync.len() + header + self.checksum.width()
+    }
+
+    /// 이 형식으로 보낼 수 있는 최대 DATA 길이
+    pub fn max_payload(&self) -> usize {
+        let max_len = if self.length_width == 2 {
+            u16::MAX as usize
+        } else {
+            u8::MAX as usize
+        };
+        if self.length_includes_overhead {
+            max_len.saturating_sub(self.overhead())
+        } else {
+            max_len
+        }
+    }
+
+    fn length_for(&self, payload_len: usize) -> u16 {
+        if self.length_includes_overhead {
+            (payload_len + self.overhead()) as u16
+        } else {
+            payload_len as u16
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum FrameSpecError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for FrameSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameSpecError::Io(e) => write!(f, "cannot read frame spec : {}", e),
+            FrameSpecError::Toml(e) => write!(f, "invalid frame spec : {}", e),
+            FrameSpecError::Invalid(reason) => write!(f, "invalid frame spec : {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for FrameSpecError {}
+
+/// `PACKET::parse` 에 바이트를 하나 넣었을 때의 결과
+#[derive(Debug, Clone)]
+pub enum ParseEvent {
+    /// 프레임을 조립하는 중이거나 STX 를 기다리는 중
+    Incomplete,
+    /// checksum 까지 확인된 프레임
+    Frame(PACKET),
+    /// 프레임이 버려진 이유
+    Error(ParseError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// CS 가 맞지 않는 프레임. `raw` 는 수신한 그대로의 바이트
+    ChecksumMismatch {
+        expected: u16,
+        got: u16,
+        raw: Vec<u8>,
+    },
+    /// LEN 이 헤더 + CS 길이보다 작은 프레임
+    InvalidLength(u16),
+    /// STX 를 찾기 전까지 버려진 바이트 수
+    DiscardedBytes(usize),
+    /// 바이트 간격이 timeout 을 넘어서 버린 조립 중 프레임
+    Timeout { discarded: usize },
+    /// escape 된 프레임이 패킷 중간에 끝남
+    Truncated { discarded: usize },
+    /// escape 규칙에 맞지 않는 바이트열
+    Framing(&'static str),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::ChecksumMismatch { expected, got, raw } => {
+                write!(
+                    f,
+                    "checksum mismatch (expected {:02X}, got {:02X}) :",
+                    expected, got
+                )?;
+                for byte in raw {
+                    write!(f, " {:02X}", byte)?;
+                }
+                Ok(())
+            }
+            ParseError::InvalidLength(len) => write!(f, "invalid length {:02X}", len),
+            ParseError::DiscardedBytes(n) => write!(f, "discarded {} bytes before STX", n),
+            ParseError::Timeout { discarded } => {
+                write!(f, "inter-byte timeout, dropped {} bytes", discarded)
+            }
+            ParseError::Truncated { discarded } => {
+                write!(f, "frame ended early, dropped {} bytes", discarded)
+            }
+            ParseError::Framing(reason) => write!(f, "framing error : {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// 수신 쓰레드의 디코더 설정. GUI 에서 바꾸면 다음 수신부터 적용된다
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DecoderConfig {
+    pub spec: FrameSpec,
+    pub resync: bool,
+    /// `None` 이면 바이트 간격을 검사하지 않는다
+    pub timeout: Option<Duration>,
+}
+
+/// 디코더가 지금까지 처리한 결과 통계
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecoderStats {
+    pub frames: u64,
+    pub checksum_errors: u64,
+    pub length_errors: u64,
+    /// STX 를 찾는 동안 건너뛴 바이트 수
+    pub skipped_bytes: u64,
+    /// 깨진 프레임 안에서 다시 STX 를 찾은 횟수
+    pub resyncs: u64,
+    /// 바이트 간격이 길어서 버린 프레임 수
+    pub timeouts: u64,
+}
+
+/// 바이트 뭉치를 받아서 완성된 프레임을 꺼내주는 디코더.
+/// 프레임이 여러 번의 `feed` 에 걸쳐 나뉘어 들어와도 이어서 조립한다.
+///
+/// resync 모드에서는 깨진 프레임의 STX 다음 바이트부터 다시 훑어서
+/// 프레임 안에 섞여 있던 STX 로 시작하는 다음 패킷을 살린다.
+///
+/// timeout 을 설정하면 조립 중에 바이트 간격이 그보다 길어졌을 때
+/// (장치가 프레임 중간에 리셋된 경우 등) 조립 중인 프레임을 버린다.
+#[derive(Debug, Clone)]
+pub struct Decoder {
+    packet: PACKET,
+    pending: Vec<u8>,
+    deframer: Deframer,
+    resync: bool,
+    timeout: Option<Duration>,
+    last_byte: Option<Instant>,
+    stats: DecoderStats,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder {
+    pub fn new() -> Decoder {
+        Decoder {
+            packet: PACKET::new(),
+            pending: Vec::new(),
+            deframer: Deframer::new(Framing::None),
+            resync: false,
+            timeout: None,
+            last_byte: None,
+            stats: DecoderStats::default(),
+        }
+    }
+
+    /// 프레임 형식을 바꾸면 조립 중이던 프레임은 버린다
+    pub fn set_spec(&mut self, spec: &FrameSpec) {
+        if self.packet.spec() != spec {
+            self.packet = PACKET::with_spec(spec.clone());
+            self.pending.clear();
+            self.deframer = Deframer::new(spec.framing);
+        }
+    }
+
+    pub fn set_resync(&mut self, enabled: bool) {
+        self.resync = enabled;
+    }
+
+    /// `None` 이면 바이트 간격을 검사하지 않는다
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    pub fn configure(&mut self, config: &DecoderConfig) {
+        self.set_spec(&config.spec);
+        self.set_resync(config.resync);
+        self.set_timeout(config.timeout);
+    }
+
+    pub fn stats(&self) -> &DecoderStats {
+        &self.stats
+    }
+
+    /// 조립 중인 프레임만 버린다. 통계는 그대로 둔다
+    pub fn reset(&mut self) {
+        let spec = self.packet.spec().clone();
+        self.deframer = Deframer::new(spec.framing);
+        self.packet = PACKET::with_spec(spec);
+        self.pending.clear();
+        self.last_byte = None;
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> impl Iterator<Item = Result<PACKET, ParseError>> {
+        self.feed_at(bytes, Instant::now())
+    }
+
+    /// `bytes` 가 `now` 시각에 수신된 것으로 보고 디코딩한다
+    pub fn feed_at(
+        &mut self,
+        bytes: &[u8],
+        now: Instant,
+    ) -> impl Iterator<Item = Result<PACKET, ParseError>> {
+        let mut results = Vec::new();
+        if bytes.is_empty() {
+            return results.into_iter();
+        }
+
+        if let (Some(timeout), Some(last_byte)) = (self.timeout, self.last_byte) {
+            let in_frame = !self.packet.is_idle() || self.deframer.in_frame();
+            if in_frame && now.saturating_duration_since(last_byte) > timeout {
+                self.stats.timeouts += 1;
+                results.push(Err(ParseError::Timeout {
+                    discarded: self.pending.len() + self.deframer.buffered(),
+                }));
+                self.packet.clear();
+                self.pending.clear();
+                self.deframer.clear();
+            }
+        }
+        self.last_byte = Some(now);
+
+        if self.packet.spec().framing == Framing::None {
+            self.parse_bytes(bytes, &mut results);
+            return results.into_iter();
+        }
+
+        // escape 된 프레임은 먼저 풀어낸 뒤 한 덩어리씩 파싱한다
+        for &byte in bytes {
+            match self.deframer.push(byte) {
+                Some(Ok(block)) => {
+                    self.parse_bytes(&block, &mut results);
+                    if !self.packet.is_idle() {
+                        results.push(Err(ParseError::Truncated {
+                            discarded: self.pending.len(),
+                        }));
+                        self.packet.clear();
+                        self.pending.clear();
+                    }
+                }
+                Some(Err(e)) => results.push(Err(e)),
+                None => {}
+            }
+        }
+
+        results.into_iter()
+    }
+
+    fn parse_bytes(&mut self, bytes: &[u8], results: &mut Vec<Result<PACKET, ParseError>>) {
+        let mut input: VecDeque<u8> = bytes.iter().copied().collect();
+
+        while let Some(byte) = input.pop_front() {
+            self.pending.push(byte);
+
+            match self.packet.parse(byte) {
+                ParseEvent::Frame(packet) => {
+                    self.stats.frames += 1;
+                    self.pending.clear();
+                    results.push(Ok(packet));
+                }
+                ParseEvent::Error(ParseError::DiscardedBytes(n)) => {
+                    self.stats.skipped_bytes += n as u64;
+                    self.trim_pending();
+                    results.push(Err(ParseError::DiscardedBytes(n)));
+                }
+                ParseEvent::Error(e) => {
+                    match e {
+                        ParseError::ChecksumMismatch { .. } => self.stats.checksum_errors += 1,
+                        ParseError::InvalidLength(_) => self.stats.length_errors += 1,
+                        _ => {}
+                    }
+
+                    if self.resync {
+                        // 깨진 프레임의 첫 sync 바이트는 버리고 나머지를 입력 앞쪽에 되돌린다
+                        self.stats.resyncs += 1;
+                        for &b in self.pending[1..].iter().rev() {
+                            input.push_front(b);
+                        }
+                    }
+                    self.pending.clear();
+                    results.push(Err(e));
+                }
+                ParseEvent::Incomplete => self.trim_pending(),
+            }
+        }
+    }
+
+    // 조립 중인 프레임에 속한 바이트만 남긴다
+    fn trim_pending(&mut self) {
+        let keep = self.packet.received();
+        if self.pending.len() > keep {
+            self.pending.drain(..self.pending.len() - keep);
+        }
+    }
+}
+
+const DLE: u8 = 0x10;
+const DLE_STX: u8 = 0x02;
+const DLE_ETX: u8 = 0x03;
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+/// 패킷을 선로에 실을 때 감싸는 방식
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
+pub enum Framing {
+    /// 패킷을 그대로 보낸다 (기존 형식)
+    #[default]
+    None,
+    /// `DLE STX .. DLE ETX`, DATA 안의 DLE 는 두 번 보낸다
+    Dle,
+    /// RFC 1055 SLIP, 프레임 끝에 END(C0)
+    Slip,
+    /// COBS, 프레임 끝에 00
+    Cobs,
+}
+
+impl Framing {
+    pub fn encode(&self, frame: &[u8]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(frame.len() + 4);
+
+        match *self {
+            Framing::None => out.extend(frame),
+            Framing::Dle => {
+                out.extend([DLE, DLE_STX]);
+                for &byte in frame {
+                    if byte == DLE {
+                        out.push(DLE);
+                    }
+                    out.push(byte);
+                }
+                out.extend([DLE, DLE_ETX]);
+            }
+            Framing::Slip => {
+                out.push(SLIP_END);
+                for &byte in frame {
+                    match byte {
+                        SLIP_END => out.extend([SLIP_ESC, SLIP_ESC_END]),
+                        SLIP_ESC => out.extend([SLIP_ESC, SLIP_ESC_ESC]),
+                        _ => out.push(byte),
+                    }
+                }
+                out.push(SLIP_END);
+            }
+            Framing::Cobs => {
+                // 0 이 아닌 바이트를 최대 254개씩 묶고, 각 묶음 앞에 다음 0 까지의 거리를 적는다
+                let mut code_idx = out.len();
+                out.push(0);
+                let mut code: u8 = 1;
+
+                for &byte in frame {
+                    if byte == 0 {
+                        out[code_idx] = code;
+                        code_idx = out.len();
+                        out.push(0);
+                        code = 1;
+                    } else {
+                        out.push(byte);
+                        code += 1;
+                        if code == 0xFF {
+                            out[code_idx] = code;
+                            code_idx = out.len();
+                            out.push(0);
+                            code = 1;
+                        }
+                    }
+                }
+                out[code_idx] = code;
+                out.push(0x00);
+            }
+        }
+
+        out
+    }
+}
+
+impl fmt::Display for Framing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Framing::None => write!(f, "None"),
+            Framing::Dle => write!(f, "DLE"),
+            Framing::Slip => write!(f, "SLIP"),
+            Framing::Cobs => write!(f, "COBS"),
+        }
+    }
+}
+
+/// 수신 바이트에서 `Framing` 을 풀어 패킷 한 개 분량의 바이트를 꺼낸다
+#[derive(Debug, Clone)]
+pub struct Deframer {
+    framing: Framing,
+    buf: Vec<u8>,
+    in_frame: bool,
+    escape: bool,
+}
+
+impl Deframer {
+    pub fn new(framing: Framing) -> Deframer {
+        Deframer {
+            framing,
+            buf: Vec::new(),
+            in_frame: false,
+            escape: false,
+        }
+    }
+
+    /// 프레임 중간까지 받은 상태인지
+    pub fn in_frame(&self) -> bool {
+        self.in_frame
+    }
+
+    pub fn buffered(&self) -> usize {
+        self.buf.len()
+    }
+
+    pub fn clear(&mut self) {
+        self.buf.clear();
+        self.in_frame = false;
+        self.escape = false;
+    }
+
+    /// 바이트 하나를 넣고, 프레임이 끝났으면 escape 를 푼 내용을 돌려준다
+    pub fn push(&mut self, byte: u8) -> Option<Result<Vec<u8>, ParseError>> {
+        match self.framing {
+            Framing::None => Some(Ok(vec![byte])),
+            Framing::Dle => self.push_dle(byte),
+            Framing::Slip => self.push_slip(byte),
+            Framing::Cobs => self.push_cobs(byte),
+        }
+    }
+
+    fn push_dle(&mut self, byte: u8) -> Option<Result<Vec<u8>, ParseError>> {
+        if !self.escape && byte == DLE {
+            self.escape = true;
+            return None;
+        }
+
+        if self.escape {
+            self.escape = false;
+            match byte {
+                DLE_STX => {
+                    let restarted = self.in_frame;
+                    self.buf.clear();
+                    self.in_frame = true;
+                    if restarted {
+                        return Some(Err(ParseError::Framing("DLE STX inside frame")));
+                    }
+                }
+                DLE_ETX if self.in_frame => {
+                    self.in_frame = false;
+                    return Some(Ok(std::mem::take(&mut self.buf)));
+                }
+                DLE if self.in_frame => self.buf.push(DLE),
+                _ if self.in_frame => {
+                    self.clear();
+                    return Some(Err(ParseError::Framing("invalid DLE sequence")));
+                }
+                // 프레임 밖의 DLE 조합은 무시
+                _ => {}
+            }
+        } else if self.in_frame {
+            self.buf.push(byte);
+        }
+
+        None
+    }
+
+    fn push_slip(&mut self, byte: u8) -> Option<Result<Vec<u8>, ParseError>> {
+        if byte == SLIP_END {
+            self.escape = false;
+            self.in_frame = false;
+            if self.buf.is_empty() {
+                return None;
+            }
+            return Some(Ok(std::mem::take(&mut self.buf)));
+        }
+
+        self.in_frame = true;
+        if self.escape {
+            self.escape = false;
+            match byte {
+                SLIP_ESC_END => self.buf.push(SLIP_END),
+                SLIP_ESC_ESC => self.buf.push(SLIP_ESC),
+                _ => {
+                    self.buf.clear();
+                    return Some(Err(ParseError::Framing("invalid SLIP escape")));
+                }
+            }
+        } else if byte == SLIP_ESC {
+            self.escape = true;
+        } else {
+            self.buf.push(byte);
+        }
+
+        None
+    }
+
+    fn push_cobs(&mut self, byte: u8) -> Option<Result<Vec<u8>, ParseError>> {
+        if byte != 0x00 {
+            self.in_frame = true;
+            self.buf.push(byte);
+            return None;
+        }
+
+        self.in_frame = false;
+        if self.buf.is_empty() {
+            return None;
+        }
+
+        let block = std::mem::take(&mut self.buf);
+        let mut out = Vec::with_capacity(block.len());
+        let mut idx = 0;
+        while idx < block.len() {
+            let code = block[idx] as usize;
+            if idx + code > block.len() {
+                return Some(Err(ParseError::Framing("COBS block overrun")));
+            }
+            out.extend(&block[idx + 1..idx + code]);
+            idx += code;
+            if code < 0xFF && idx < block.len() {
+                out.push(0x00);
+            }
+        }
+
+        Some(Ok(out))
+    }
+}
+
+/// `04 78`, `0478`, `0x04, 0x78` 같은 hex 문자열을 바이트로 바꾼다.
+/// 공백이나 `,` 로 나눈 한 덩어리가 1자리면 한 바이트, 그보다 길면 2자리씩 읽는다
+///
+/// ```
+/// use RUST_tutorial::protocol::parse_hex;
+///
+/// assert_eq!(parse_hex("04 78"), Some(vec![0x04, 0x78]));
+/// assert_eq!(parse_hex("0x4,0478"), Some(vec![0x04, 0x04, 0x78]));
+/// assert_eq!(parse_hex(""), Some(vec![]));
+/// assert_eq!(parse_hex("123"), None);
+/// ```
+pub fn parse_hex(text: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+
+    for word in text.split(|c: char| c == ',' || c.is_whitespace()) {
+        let digits = word
+            .strip_prefix("0x")
+            .or_else(|| word.strip_prefix("0X"))
+            .unwrap_or(word);
+        if !digits.is_ascii() {
+            return None;
+        }
+        match digits.len() {
+            0 => {}
+            1 => bytes.push(u8::from_str_radix(digits, 16).ok()?),
+            n if n % 2 == 0 => {
+                for i in (0..n).step_by(2) {
+                    bytes.push(u8::from_str_radix(&digits[i..i + 2], 16).ok()?);
+                }
+            }
+            _ => return None,
+        }
+    }
+
+    Some(bytes)
+}
+
+/// STX 부터 마지막 DATA 까지(CS 제외)의 바이트로 checksum 을 계산한다.
+/// STX 로 시작해서 이후 바이트마다 XOR 한 뒤 1을 더한다.
+pub fn calc_cs(frame: &[u8]) -> u8 {
+    let mut cs: u8 = STX;
+    for byte in frame.iter().skip(1) {
+        cs ^= byte;
+        cs = cs.wrapping_add(1);
+    }
+
+    cs
+}
+
+/// 프레임 CS 알고리즘.
+/// `compute` 에는 STX 부터 CS 바로 앞까지의 바이트가 들어온다.
+pub trait Checksum {
+    /// 프레임 끝에 붙는 CS 바이트 수
+    fn width(&self) -> usize;
+
+    fn compute(&self, frame: &[u8]) -> u16;
+
+    /// CS 값을 프레임에 붙일 바이트로 바꾼다. 2바이트는 하위 바이트가 먼저
+    fn encode(&self, value: u16) -> Vec<u8> {
+        value.to_le_bytes()[..self.width()].to_vec()
+    }
+}
+
+/// 기존 펌웨어의 CS. STX 로 시작해서 바이트마다 XOR 후 1 증가
+#[derive(Debug, Clone, Copy, Default)]
+pub struct XorIncrement;
+
+impl Checksum for XorIncrement {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn compute(&self, frame: &[u8]) -> u16 {
+        calc_cs(frame) as u16
+    }
+}
+
+/// 모든 바이트의 합 (하위 8비트)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Sum8;
+
+impl Checksum for Sum8 {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn compute(&self, frame: &[u8]) -> u16 {
+        frame.iter().fold(0u8, |cs, b| cs.wrapping_add(*b)) as u16
+    }
+}
+
+/// 모든 바이트의 XOR
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Xor8;
+
+impl Checksum for Xor8 {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn compute(&self, frame: &[u8]) -> u16 {
+        frame.iter().fold(0u8, |cs, b| cs ^ b) as u16
+    }
+}
+
+/// CRC-8 (poly 0x07, init 0x00)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc8;
+
+impl Checksum for Crc8 {
+    fn width(&self) -> usize {
+        1
+    }
+
+    fn compute(&self, frame: &[u8]) -> u16 {
+        let mut crc: u8 = 0x00;
+        for byte in frame {
+            crc ^= byte;
+            for _ in 0..8 {
+                crc = if crc & 0x80 != 0 {
+                    (crc << 1) ^ 0x07
+                } else {
+                    crc << 1
+                };
+            }
+        }
+
+        crc as u16
+    }
+}
+
+/// CRC-16/MODBUS (poly 0xA001 reflected, init 0xFFFF), 2바이트 CS
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Crc16Modbus;
+
+impl Checksum for Crc16Modbus {
+    fn width(&self) -> usize {
+        2
+    }
+
+    fn compute(&self, frame: &[u8]) -> u16 {
+        let mut crc: u16 = 0xFFFF;
+        for byte in frame {
+            crc ^= *byte as u16;
+            for _ in 0..8 {
+                crc = if crc & 0x0001 != 0 {
+                    (crc >> 1) ^ 0xA001
+                } else {
+                    crc >> 1
+                };
+            }
+        }
+
+        crc
+    }
+}
+
+/// 세션마다 고를 수 있는 CS 알고리즘 목록 (앱 설정으로 저장된다)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
+pub enum ChecksumKind {
+    #[default]
+    XorIncrement,
+    Sum8,
+    Xor8,
+    Crc8,
+    Crc16Modbus,
+}
+
+impl ChecksumKind {
+    pub fn algorithm(&self) -> &'static dyn Checksum {
+        match *self {
+            ChecksumKind::XorIncrement => &XorIncrement,
+            ChecksumKind::Sum8 => &Sum8,
+            ChecksumKind::Xor8 => &Xor8,
+            ChecksumKind::Crc8 => &Crc8,
+            ChecksumKind::Crc16Modbus => &Crc16Modbus,
+        }
+    }
+}
+
+impl Checksum for ChecksumKind {
+    fn width(&self) -> usize {
+        self.algorithm().width()
+    }
+
+    fn compute(&self, frame: &[u8]) -> u16 {
+        self.algorithm().compute(frame)
+    }
+}
+
+impl fmt::Display for ChecksumKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ChecksumKind::XorIncrement => write!(f, "XOR+1"),
+            ChecksumKind::Sum8 => write!(f, "SUM8"),
+            ChecksumKind::Xor8 => write!(f, "XOR8"),
+            ChecksumKind::Crc8 => write!(f, "CRC-8"),
+            ChecksumKind::Crc16Modbus => write!(f, "CRC-16/MODBUS"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    PayloadTooLong { len: usize, max: usize },
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            BuildError::PayloadTooLong { len, max } => {
+                write!(f, "payload is {} bytes, max is {}", len, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+/// ID, CMD, SEQ, DATA 만 지정하면 LEN 과 CS 를 채워서 패킷을 만든다.
+///
+/// ```
+/// use RUST_tutorial::protocol::PacketBuilder;
+///
+/// let bytes = PacketBuilder::new()
+///     .id(0xC1)
+///     .command(0x12)
+///     .sequence(0x00)
+///     .payload(&[0x04, 0x78])
+///     .to_bytes()
+///     .unwrap();
+/// assert_eq!(bytes, [0x02, 0xC1, 0x08, 0x12, 0x00, 0x04, 0x78, 0x9F]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct PacketBuilder<'a> {
+    id: u8,
+    command: u8,
+    sequence: u8,
+    payload: &'a [u8],
+    spec: FrameSpec,
+}
+
+impl<'a> PacketBuilder<'a> {
+    pub fn new() -> PacketBuilder<'a> {
+        PacketBuilder::default()
+    }
+
+    pub fn id(mut self, id: u8) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn command(mut self, command: u8) -> Self {
+        self.command = command;
+        self
+    }
+
+    pub fn sequence(mut self, sequence: u8) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    pub fn payload(mut self, payload: &'a [u8]) -> Self {
+        self.payload = payload;
+        self
+    }
+
+    pub fn checksum(mut self, cs_kind: ChecksumKind) -> Self {
+        self.spec.checksum = cs_kind;
+        self
+    }
+
+    /// 기본 형식 대신 `spec` 형식으로 만든다. SEQ 가 없는 형식이면 sequence 는 무시된다
+    pub fn spec(mut self, spec: &FrameSpec) -> Self {
+        self.spec = spec.clone();
+        self
+    }
+
+    pub fn build(&self) -> Result<PACKET, BuildError> {
+        let max = self.spec.max_payload();
+        if self.payload.len() > max {
+            return Err(BuildError::PayloadTooLong {
+                len: self.payload.len(),
+                max,
+            });
+        }
+
+        let mut packet = PACKET::with_spec(self.spec.clone());
+        packet.header.id = self.id;
+        packet.header.length = self.spec.length_for(self.payload.len());
+        packet.header.command = self.command;
+        packet.header.sequence = self.sequence;
+        packet.data = self.payload.to_vec();
+
+        let bytes = packet.serialize();
+        let cs_start = bytes.len() - self.spec.checksum.width();
+        packet.checksum = self.spec.checksum.compute(&bytes[..cs_start]);
+
+        Ok(packet)
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, BuildError> {
+        self.build().map(|packet| packet.serialize())
+    }
+
+    /// framing 까지 적용한 송신 바이트
+    pub fn to_wire(&self) -> Result<Vec<u8>, BuildError> {
+        self.build().map(|packet| packet.to_wire())
     }
 }
@@ -0,0 +1,277 @@
+use log::{debug, trace};
+use std::net::TcpStream;
+
+use crate::serial::{DataBits, FlowControl, Parity, SerialConfig, SerialError, StopBits};
+use crate::transport::{clone_writer, TcpTransport, Transport, TransportWriter};
+
+// Telnet (RFC 854)
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+
+const OPT_BINARY: u8 = 0;
+const OPT_SGA: u8 = 3;
+const OPT_COM_PORT: u8 = 44;
+
+// COM-PORT-OPTION 명령 (client -> server). server 는 +100 으로 응답한다
+const SET_BAUDRATE: u8 = 1;
+const SET_DATASIZE: u8 = 2;
+const SET_PARITY: u8 = 3;
+const SET_STOPSIZE: u8 = 4;
+const SET_CONTROL: u8 = 5;
+const SERVER_OFFSET: u8 = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    Data,
+    Iac,
+    Option(u8),
+    Sub,
+    SubIac,
+}
+
+/// 수신 스트림에서 Telnet 명령을 걷어내고 데이터만 남긴다
+///
+/// ```
+/// use RUST_tutorial::rfc2217::Telnet;
+///
+/// let mut telnet = Telnet::new();
+/// // 0xFF 데이터(IAC IAC), 모르는 옵션 요청(IAC DO 24), STX
+/// let (data, reply) = telnet.decode(&[0xFF, 0xFF, 0xFF, 0xFD, 24, 0x02]);
+/// assert_eq!(data, [0xFF, 0x02]);
+/// assert_eq!(reply, [0xFF, 0xFC, 24]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Telnet {
+    step: Step,
+    sub: Vec<u8>,
+}
+
+impl Default for Telnet {
+    fn default() -> Self {
+        Telnet::new()
+    }
+}
+
+impl Telnet {
+    pub fn new() -> Telnet {
+        Telnet {
+            step: Step::Data,
+            sub: Vec::new(),
+        }
+    }
+
+    /// `input` 을 데이터와 server 에 돌려줄 응답으로 나눈다.
+    /// 명령이 read 경계에서 잘려도 다음 호출에서 이어서 처리한다
+    pub fn decode(&mut self, input: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        let mut data = Vec::with_capacity(input.len());
+        let mut reply = Vec::new();
+
+        for &byte in input {
+            self.step = match (self.step, byte) {
+                (Step::Data, IAC) => Step::Iac,
+                (Step::Data, _) => {
+                    data.push(byte);
+                    Step::Data
+                }
+                // IAC IAC 는 데이터 0xFF
+                (Step::Iac, IAC) => {
+                    data.push(IAC);
+                    Step::Data
+                }
+                (Step::Iac, DO | DONT | WILL | WONT) => Step::Option(byte),
+                (Step::Iac, SB) => {
+                    self.sub.clear();
+                    Step::Sub
+                }
+                // NOP, GA 등 나머지 2 byte 명령은 무시
+                (Step::Iac, _) => Step::Data,
+                (Step::Option(command), option) => {
+                    reply.extend(Telnet::answer(command, option));
+                    Step::Data
+                }
+                (Step::Sub, IAC) => Step::SubIac,
+                (Step::Sub, _) => {
+                    self.sub.push(byte);
+                    Step::Sub
+                }
+                (Step::SubIac, SE) => {
+                    self.subnegotiation();
+                    Step::Data
+                }
+                (Step::SubIac, _) => {
+                    self.sub.push(byte);
+                    Step::Sub
+                }
+            };
+        }
+
+        (data, reply)
+    }
+
+    // 우리가 먼저 요청한 옵션 (BINARY, SGA, COM-PORT) 만 받아들이고 나머지는 거절한다
+    fn answer(command: u8, option: u8) -> Vec<u8> {
+        let supported = matches!(option, OPT_BINARY | OPT_SGA | OPT_COM_PORT);
+        trace!("Telnet {} {}", command, option);
+        match command {
+            DO if !supported => vec![IAC, WONT, option],
+            WILL if !supported => vec![IAC, DONT, option],
+            _ => Vec::new(),
+        }
+    }
+
+    fn subnegotiation(&mut self) {
+        match self.sub.as_slice() {
+            [OPT_COM_PORT, command, value @ ..] if *command > SERVER_OFFSET => {
+                debug!(
+                    "RFC 2217 server : command {} = {:02X?}",
+                    command - SERVER_OFFSET,
+                    value
+                );
+            }
+            sub => trace!("Telnet SB {:02X?}", sub),
+        }
+    }
+
+    /// 데이터의 0xFF 를 IAC IAC 로 바꾼다
+    pub fn escape(data: &[u8]) -> Vec<u8> {
+        let mut escaped = Vec::with_capacity(data.len());
+        for &byte in data {
+            if byte == IAC {
+                escaped.push(IAC);
+            }
+            escaped.push(byte);
+        }
+        escaped
+    }
+}
+
+// IAC SB COM-PORT-OPTION <command> <value> IAC SE
+fn com_port_command(command: u8, value: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![IAC, SB, OPT_COM_PORT, command];
+    bytes.extend(Telnet::escape(value));
+    bytes.extend([IAC, SE]);
+    bytes
+}
+
+/// 접속 직후 보내는 옵션 협상과 회선 설정
+fn negotiation(config: &SerialConfig) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend([IAC, WILL, OPT_BINARY]);
+    bytes.extend([IAC, DO, OPT_BINARY]);
+    bytes.extend([IAC, DO, OPT_SGA]);
+    bytes.extend([IAC, WILL, OPT_COM_PORT]);
+
+    let data_size = match config.data_bits {
+        DataBits::Five => 5,
+        DataBits::Six => 6,
+        DataBits::Seven => 7,
+        DataBits::Eight => 8,
+    };
+    let parity = match config.parity {
+        Parity::None => 1,
+        Parity::Odd => 2,
+        Parity::Even => 3,
+    };
+    let stop_size = match config.stop_bits {
+        StopBits::One => 1,
+        StopBits::Two => 2,
+    };
+    let control = match config.flow_control {
+        FlowControl::None => 1,
+        FlowControl::Software => 2,
+        FlowControl::Hardware => 3,
+    };
+
+    bytes.extend(com_port_command(
+        SET_BAUDRATE,
+        &config.baud_rate.to_be_bytes(),
+    ));
+    bytes.extend(com_port_command(SET_DATASIZE, &[data_size]));
+    bytes.extend(com_port_command(SET_PARITY, &[parity]));
+    bytes.extend(com_port_command(SET_STOPSIZE, &[stop_size]));
+    bytes.extend(com_port_command(SET_CONTROL, &[control]));
+
+    bytes
+}
+
+// 데이터의 0xFF 를 이스케이프해서 보내는 송신 핸들
+struct TelnetWriter(TcpStream);
+
+impl TransportWriter for TelnetWriter {
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        TransportWriter::write(&mut self.0, &Telnet::escape(data))
+    }
+}
+
+/// RFC 2217 (Telnet COM-PORT-OPTION) 로 원격 포트의 회선 설정까지 맞추는 TCP 클라이언트.
+/// ser2net 의 telnet 포트, Moxa 의 RFC 2217 모드 등에 접속한다
+pub struct Rfc2217Transport {
+    tcp: TcpTransport,
+    config: SerialConfig,
+    telnet: Telnet,
+}
+
+impl Rfc2217Transport {
+    pub fn connect(address: &str, config: &SerialConfig) -> Result<Rfc2217Transport, SerialError> {
+        let mut transport = Rfc2217Transport {
+            tcp: TcpTransport::connect(address)?,
+            config: *config,
+            telnet: Telnet::new(),
+        };
+        transport.negotiate()?;
+
+        Ok(transport)
+    }
+
+    fn negotiate(&mut self) -> Result<(), SerialError> {
+        self.telnet = Telnet::new();
+        self.tcp.write(&negotiation(&self.config))
+    }
+}
+
+impl Transport for Rfc2217Transport {
+    fn name(&self) -> String {
+        format!("rfc2217://{} ({})", self.tcp.address(), self.config)
+    }
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        let bytes = self.tcp.read()?;
+        let (data, reply) = self.telnet.decode(&bytes);
+        if !reply.is_empty() {
+            self.tcp.write(&reply)?;
+        }
+
+        Ok(data)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        self.tcp.write(&Telnet::escape(data))
+    }
+
+    fn close(&mut self) {
+        self.tcp.close();
+    }
+
+    fn is_open(&self) -> bool {
+        self.tcp.is_open()
+    }
+
+    fn writer(&self) -> Option<Box<dyn TransportWriter>> {
+        let stream = self.tcp.stream()?.try_clone();
+        clone_writer(&self.name(), stream.map(TelnetWriter))
+    }
+
+    fn can_reopen(&self) -> bool {
+        true
+    }
+
+    fn reopen(&mut self) -> Result<(), SerialError> {
+        self.tcp.reopen()?;
+        self.negotiate()
+    }
+}
@@ -0,0 +1,172 @@
+use log::{info, warn};
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::packet_log::{Direction, LogEntry};
+use crate::protocol::PACKET;
+use crate::session::SessionEvent;
+use crate::transport::SharedTransport;
+
+// 기다리는 동안 멈춤 요청을 확인하는 최대 간격
+const STOP_POLL: Duration = Duration::from_millis(20);
+
+/// 반복 전송 설정
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    pub packet: PACKET,
+    pub interval: Duration,
+    /// None 이면 멈출 때까지 보낸다
+    pub count: Option<u32>,
+    /// 보낼 때마다 SEQ 를 1 씩 올린다
+    pub increment_sequence: bool,
+    /// SEQ 를 올린 뒤 CS 를 다시 계산한다. CS 를 직접 입력했으면 그대로 둔다
+    pub recompute_checksum: bool,
+}
+
+/// 같은 프레임을 주기적으로 보내는 쓰레드. drop 되면 멈춘다.
+/// 보낸 프레임은 `SessionEvent::Packet` 으로, 끝나면 `SessionEvent::SendDone` 으로 알린다
+pub struct Scheduler {
+    running: Arc<AtomicBool>,
+    sent: Arc<AtomicU32>,
+    count: Option<u32>,
+    thread: Option<thread::JoinHandle<()>>,
+}
+
+impl Scheduler {
+    pub fn start(
+        schedule: Schedule,
+        transport: Arc<SharedTransport>,
+        event_tx: mpsc::Sender<SessionEvent>,
+    ) -> Scheduler {
+        let running = Arc::new(AtomicBool::new(true));
+        let sent = Arc::new(AtomicU32::new(0));
+        let count = schedule.count;
+        info!(
+            "Repeat send started : every {:?}, {}",
+            schedule.interval,
+            count.map_or(String::from("until stopped"), |count| format!(
+                "{} times",
+                count
+            ))
+        );
+
+        let thread = spawn_send_thread(
+            schedule,
+            transport,
+            event_tx,
+            Arc::clone(&running),
+            Arc::clone(&sent),
+        );
+
+        Scheduler {
+            running,
+            sent,
+            count,
+            thread: Some(thread),
+        }
+    }
+
+    /// 지금까지 보낸 수
+    pub fn sent(&self) -> u32 {
+        self.sent.load(Ordering::Relaxed)
+    }
+
+    pub fn count(&self) -> Option<u32> {
+        self.count
+    }
+
+    /// 다 보냈거나 오류로 끝났으면 false
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+fn spawn_send_thread(
+    schedule: Schedule,
+    transport: Arc<SharedTransport>,
+    event_tx: mpsc::Sender<SessionEvent>,
+    running: Arc<AtomicBool>,
+    sent: Arc<AtomicU32>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut packet = schedule.packet;
+        let mut count = 0;
+        // 보낸 시각이 아니라 예정 시각에 interval 을 더해서 누적 오차가 없게 한다
+        let mut next = Instant::now();
+
+        while running.load(Ordering::Relaxed) && schedule.count.map_or(true, |total| count < total)
+        {
+            if !sleep_until(next, &running) {
+                break;
+            }
+
+            let result = transport.write(&packet.to_wire());
+            if let Err(e) = result {
+                warn!("Repeat send failed : {}", e);
+                let _ = event_tx.send(SessionEvent::SendFailed(e));
+                break;
+            }
+            count += 1;
+            sent.store(count, Ordering::Relaxed);
+            let _ = event_tx.send(SessionEvent::Packet(LogEntry::from_packet(
+                Direction::Tx,
+                &packet,
+            )));
+
+            if schedule.increment_sequence {
+                packet.header.sequence = packet.header.sequence.wrapping_add(1);
+                if schedule.recompute_checksum {
+                    packet.checksum = packet.expected_cs();
+                }
+            }
+
+            next += schedule.interval;
+            // 한 주기 넘게 밀렸으면 (포트 잠김 등) 몰아서 보내지 않고 지금부터 다시 맞춘다
+            let now = Instant::now();
+            if now > next + schedule.interval {
+                next = now;
+            }
+        }
+
+        running.store(false, Ordering::Relaxed);
+        info!("Repeat send finished : {} sent", count);
+        let _ = event_tx.send(SessionEvent::SendDone(count));
+    })
+}
+
+// `deadline` 까지 기다린다. 그 전에 멈추라는 요청이 오면 false
+fn sleep_until(deadline: Instant, running: &AtomicBool) -> bool {
+    loop {
+        if !running.load(Ordering::Relaxed) {
+            return false;
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return true;
+        }
+        thread::sleep((deadline - now).min(STOP_POLL));
+    }
+}
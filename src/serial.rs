@@ -1,4 +1,4 @@
-use std::{fmt, time::Duration};
+use std::{fmt, io};
 
 use log::{debug, error, info, trace, warn, LevelFilter};
 use serialport::{SerialPortInfo, SerialPortType};
@@ -6,11 +6,16 @@ use serialport::{SerialPortInfo, SerialPortType};
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
+use crate::transport::{clone_writer, Transport, TransportWriter, READ_BUF_SIZE, READ_TIMEOUT};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
 pub enum BaudRate {
     B9600 = 9600,
     B38400 = 38400,
+    B57600 = 57600,
     B115200 = 115200,
+    B230400 = 230400,
+    B921600 = 921600,
 }
 
 impl fmt::Display for BaudRate {
@@ -18,29 +23,288 @@ impl fmt::Display for BaudRate {
         match *self {
             BaudRate::B9600 => write!(f, "9600"),
             BaudRate::B38400 => write!(f, "38400"),
+            BaudRate::B57600 => write!(f, "57600"),
             BaudRate::B115200 => write!(f, "115200"),
+            BaudRate::B230400 => write!(f, "230400"),
+            BaudRate::B921600 => write!(f, "921600"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
+pub enum DataBits {
+    Five,
+    Six,
+    Seven,
+    Eight,
+}
+
+impl fmt::Display for DataBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            DataBits::Five => write!(f, "5"),
+            DataBits::Six => write!(f, "6"),
+            DataBits::Seven => write!(f, "7"),
+            DataBits::Eight => write!(f, "8"),
+        }
+    }
+}
+
+impl From<DataBits> for serialport::DataBits {
+    fn from(data_bits: DataBits) -> Self {
+        match data_bits {
+            DataBits::Five => serialport::DataBits::Five,
+            DataBits::Six => serialport::DataBits::Six,
+            DataBits::Seven => serialport::DataBits::Seven,
+            DataBits::Eight => serialport::DataBits::Eight,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
+pub enum Parity {
+    None,
+    Odd,
+    Even,
+}
+
+impl Parity {
+    // 8N1 같은 표기에 쓰는 한 글자
+    pub fn letter(&self) -> char {
+        match *self {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        }
+    }
+}
+
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Parity::None => write!(f, "None"),
+            Parity::Odd => write!(f, "Odd"),
+            Parity::Even => write!(f, "Even"),
+        }
+    }
+}
+
+impl From<Parity> for serialport::Parity {
+    fn from(parity: Parity) -> Self {
+        match parity {
+            Parity::None => serialport::Parity::None,
+            Parity::Odd => serialport::Parity::Odd,
+            Parity::Even => serialport::Parity::Even,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
+pub enum StopBits {
+    One,
+    Two,
+}
+
+impl fmt::Display for StopBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            StopBits::One => write!(f, "1"),
+            StopBits::Two => write!(f, "2"),
+        }
+    }
+}
+
+impl From<StopBits> for serialport::StopBits {
+    fn from(stop_bits: StopBits) -> Self {
+        match stop_bits {
+            StopBits::One => serialport::StopBits::One,
+            StopBits::Two => serialport::StopBits::Two,
         }
     }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
-pub enum ComPort {
-    COM1 = 1,
-    COM2 = 2,
-    COM3 = 3,
-    COM4 = 4,
-    COM5 = 5,
-    COM6 = 6,
-    COM7 = 7,
-    COM8 = 8,
-    COM9 = 9,
-    COM10 = 10,
+pub enum FlowControl {
+    None,
+    Software,
+    Hardware,
+}
+
+impl fmt::Display for FlowControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            FlowControl::None => write!(f, "None"),
+            FlowControl::Software => write!(f, "XON/XOFF"),
+            FlowControl::Hardware => write!(f, "RTS/CTS"),
+        }
+    }
+}
+
+impl From<FlowControl> for serialport::FlowControl {
+    fn from(flow_control: FlowControl) -> Self {
+        match flow_control {
+            FlowControl::None => serialport::FlowControl::None,
+            FlowControl::Software => serialport::FlowControl::Software,
+            FlowControl::Hardware => serialport::FlowControl::Hardware,
+        }
+    }
+}
+
+/// 포트를 열 때 쓰는 회선 설정. 기본값은 9600 8N1, 흐름제어 없음
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SerialConfig {
+    // BaudRate 에 없는 값도 쓸 수 있도록 숫자로 저장
+    pub baud_rate: u32,
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+}
+
+impl Default for SerialConfig {
+    fn default() -> Self {
+        SerialConfig {
+            baud_rate: BaudRate::B9600 as u32,
+            data_bits: DataBits::Eight,
+            parity: Parity::None,
+            stop_bits: StopBits::One,
+            flow_control: FlowControl::None,
+        }
+    }
+}
+
+impl fmt::Display for SerialConfig {
+    /// 9600 8N1, 115200 7O2 (RTS/CTS) 형태
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}{}{}",
+            self.baud_rate,
+            self.data_bits,
+            self.parity.letter(),
+            self.stop_bits
+        )?;
+        if self.flow_control != FlowControl::None {
+            write!(f, " ({})", self.flow_control)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// `serialport::available_ports` 로 찾은 포트 정보
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct PortEntry {
+    pub port_name: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+impl PortEntry {
+    fn from_info(port: &SerialPortInfo) -> PortEntry {
+        let mut entry = PortEntry {
+            port_name: port.port_name.clone(),
+            ..Default::default()
+        };
+
+        if let SerialPortType::UsbPort(usb_port) = &port.port_type {
+            entry.vid = Some(usb_port.vid);
+            entry.pid = Some(usb_port.pid);
+            entry.manufacturer = usb_port.manufacturer.clone();
+            entry.product = usb_port.product.clone();
+            entry.serial_number = usb_port.serial_number.clone();
+        }
+
+        entry
+    }
+
+    pub fn is_usb(&self) -> bool {
+        self.vid.is_some()
+    }
+
+    /// 같은 장치인지 확인. USB 포트는 경로가 바뀔 수 있으므로 VID/PID/시리얼 번호로 비교한다
+    pub fn same_device(&self, other: &PortEntry) -> bool {
+        if !self.is_usb() {
+            return self.port_name == other.port_name;
+        }
+
+        self.vid == other.vid
+            && self.pid == other.pid
+            && match &self.serial_number {
+                Some(serial_number) => other.serial_number.as_ref() == Some(serial_number),
+                // 시리얼 번호가 없는 어댑터는 VID/PID 만으로 찾는다
+                None => true,
+            }
+    }
+}
+
+impl fmt::Display for PortEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.port_name)?;
+
+        let desc: Vec<&str> = [&self.manufacturer, &self.product]
+            .into_iter()
+            .flatten()
+            .map(|s| s.as_str())
+            .collect();
+        if !desc.is_empty() {
+            write!(f, " - {}", desc.join(" "))?;
+        }
+        if let Some(serial_number) = &self.serial_number {
+            write!(f, " ({})", serial_number)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum SerialError {
+    /// init 전이거나 이미 닫힌 포트
+    NotOpen,
+    /// 포트를 열지 못함 (없는 포트, 사용 중, 지원하지 않는 설정 등)
+    Open(serialport::Error),
+    /// 읽기/쓰기 중 IO 오류. USB 어댑터를 뽑으면 보통 여기로 온다
+    Io(io::Error),
+    /// 상대가 포트를 닫음 (EOF)
+    Disconnected,
+}
+
+impl fmt::Display for SerialError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SerialError::NotOpen => write!(f, "port is not open"),
+            SerialError::Open(e) => write!(f, "cannot open port : {}", e),
+            SerialError::Io(e) => write!(f, "port IO error : {}", e),
+            SerialError::Disconnected => write!(f, "port disconnected"),
+        }
+    }
+}
+
+impl std::error::Error for SerialError {}
+
+impl From<serialport::Error> for SerialError {
+    fn from(e: serialport::Error) -> Self {
+        SerialError::Open(e)
+    }
+}
+
+impl From<io::Error> for SerialError {
+    fn from(e: io::Error) -> Self {
+        SerialError::Io(e)
+    }
 }
 
 pub struct SERIAL {
     pub port_name: String,
-    pub baud_rate: u32,
-    pub buf: [u8; 1],
+    pub config: SerialConfig,
+    // 다시 연결할 때 찾을 장치. USB 포트는 VID/PID/시리얼 번호를 갖는다
+    pub device: PortEntry,
+    pub buf: [u8; READ_BUF_SIZE],
     pub port: Option<Box<dyn serialport::SerialPort>>,
 }
 
@@ -48,25 +312,70 @@ impl SERIAL {
     pub fn new() -> SERIAL {
         SERIAL {
             port_name: String::new(),
-            baud_rate: 0,
-            buf: [0; 1],
+            config: SerialConfig::default(),
+            device: PortEntry::default(),
+            buf: [0; READ_BUF_SIZE],
             port: None,
         }
     }
 
-    pub fn init(&mut self, port_name: &String, baud_rate: u32) {
-        let mut serial_port = serialport::new(port_name, baud_rate)
-            .timeout(Duration::from_millis(1000))
-            .open()
-            .expect("Failed to open port");
+    /// 이미 열린 포트로 만든다. (pty 등 `serialport::new` 로 열 수 없는 포트)
+    pub fn from_port(mut port: Box<dyn serialport::SerialPort>) -> Result<SERIAL, SerialError> {
+        port.set_timeout(READ_TIMEOUT)?;
+
+        let mut serial = SERIAL::new();
+        serial.port_name = port.name().unwrap_or_default();
+        serial.config.baud_rate = port.baud_rate()?;
+        serial.device.port_name = serial.port_name.clone();
+        serial.port = Some(port);
+
+        Ok(serial)
+    }
+
+    pub fn init(&mut self, port_name: &str, config: &SerialConfig) -> Result<(), SerialError> {
+        let serial_port = serialport::new(port_name, config.baud_rate)
+            .data_bits(config.data_bits.into())
+            .parity(config.parity.into())
+            .stop_bits(config.stop_bits.into())
+            .flow_control(config.flow_control.into())
+            .timeout(READ_TIMEOUT)
+            .open()?;
 
-        self.port_name = port_name.clone();
-        self.baud_rate = baud_rate;
-        self.buf = [0; 1];
+        if self.device.port_name != port_name {
+            self.device = PortEntry {
+                port_name: port_name.to_string(),
+                ..Default::default()
+            };
+        }
+        self.port_name = port_name.to_string();
+        self.config = *config;
+        self.buf = [0; READ_BUF_SIZE];
         self.port = Some(serial_port);
+
+        Ok(())
+    }
+
+    /// `device` 를 열고, 끊겼을 때 같은 장치를 찾을 수 있도록 기억해 둔다
+    pub fn open_device(
+        &mut self,
+        device: &PortEntry,
+        config: &SerialConfig,
+    ) -> Result<(), SerialError> {
+        self.init(&device.port_name, config)?;
+        self.device = device.clone();
+
+        Ok(())
+    }
+
+    pub fn close(&mut self) {
+        self.port = None;
     }
 
-    pub fn print_port_detail(&mut self, port_name: &String, usb_port: &serialport::UsbPortInfo) {
+    pub fn is_open(&self) -> bool {
+        self.port.is_some()
+    }
+
+    pub fn print_port_detail(port_name: &String, usb_port: &serialport::UsbPortInfo) {
         debug!("Port name : {}", port_name);
 
         if let Some(manufacturer) = &usb_port.manufacturer {
@@ -80,51 +389,57 @@ impl SERIAL {
         }
     }
 
-    pub fn scan_ports(&mut self) -> Vec<String> {
-        let mut port_name_list = Vec::new();
+    /// 시스템에 있는 시리얼 포트 목록. USB 포트는 VID/PID 와 제조사 정보도 채운다
+    pub fn scan_ports() -> Vec<PortEntry> {
+        let mut port_list = Vec::new();
 
         match serialport::available_ports() {
             Ok(ports) => {
                 if ports.is_empty() {
                     debug!("No ports exists");
-                } else {
-                    for (idx, port) in ports.iter().enumerate() {
-                        match port.clone().port_type {
-                            SerialPortType::UsbPort(usb_port_info) => {
-                                port_name_list.push(port.port_name.clone());
-                                debug!("Port num : {}", idx);
-                                self.print_port_detail(&port.port_name, &usb_port_info);
-                                debug!("")
-                            }
-                            SerialPortType::PciPort => {}
-                            SerialPortType::BluetoothPort => {}
-                            SerialPortType::Unknown => {}
+                }
+
+                for (idx, port) in ports.iter().enumerate() {
+                    debug!("Port num : {}", idx);
+                    match &port.port_type {
+                        SerialPortType::UsbPort(usb_port_info) => {
+                            SERIAL::print_port_detail(&port.port_name, usb_port_info);
                         }
+                        _ => debug!("Port name : {}", port.port_name),
                     }
+                    debug!("");
+
+                    port_list.push(PortEntry::from_info(port));
                 }
             }
             Err(e) => {
-                print!("Error when scanning ports {e}");
+                warn!("Error when scanning ports {e}");
             }
         }
 
-        return port_name_list;
+        port_list
     }
 
-    pub fn read(&mut self) -> u8 {
-        loop {
-            if let Some(ref mut port) = self.port {
-                match port.read(&mut self.buf) {
-                    Ok(_) => return self.buf[0],
-                    Err(ref e) if e.kind() == std::io::ErrorKind::TimedOut => {
-                        // 타임아웃 발생 시 계속 대기
-                        continue;
-                    }
-                    Err(e) => panic!("Error reading from serial port: {:?}", e),
-                }
-            } else {
-                panic!("Serial port not initialized");
-            }
+    /// `target` 과 같은 장치가 지금 연결되어 있으면 그 포트 정보를 돌려준다.
+    /// 주기적으로 불리므로 scan_ports 와 달리 로그를 남기지 않는다
+    pub fn find_port(target: &PortEntry) -> Option<PortEntry> {
+        serialport::available_ports()
+            .ok()?
+            .iter()
+            .map(PortEntry::from_info)
+            .find(|port| target.same_device(port))
+    }
+
+    /// 한 번에 읽을 수 있는 만큼 읽어서 돌려준다. (최대 READ_BUF_SIZE)
+    /// READ_TIMEOUT 동안 수신이 없으면 빈 Vec 을 돌려준다.
+    pub fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        let port = self.port.as_mut().ok_or(SerialError::NotOpen)?;
+        match port.read(&mut self.buf) {
+            // timeout 은 TimedOut 으로 오므로 0 byte 는 상대가 닫은 경우
+            Ok(0) => Err(SerialError::Disconnected),
+            Ok(n) => Ok(self.buf[..n].to_vec()),
+            Err(ref e) if e.kind() == io::ErrorKind::TimedOut => Ok(Vec::new()),
+            Err(e) => Err(SerialError::Io(e)),
         }
 
         /* OLD CODE
@@ -145,11 +460,66 @@ impl SERIAL {
          */
     }
 
-    pub fn write(&mut self, data: u8) {
-        if let Some(ref mut port) = self.port {
-            port.write(&[data]).unwrap();
+    pub fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        let port = self.port.as_mut().ok_or(SerialError::NotOpen)?;
+        port.write_all(data)?;
+
+        Ok(())
+    }
+}
+
+impl TransportWriter for Box<dyn serialport::SerialPort> {
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        self.write_all(data)?;
+
+        Ok(())
+    }
+}
+
+impl Transport for SERIAL {
+    fn name(&self) -> String {
+        format!("{} ({})", self.port_name, self.config)
+    }
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        SERIAL::read(self)
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        SERIAL::write(self, data)
+    }
+
+    fn close(&mut self) {
+        SERIAL::close(self)
+    }
+
+    fn is_open(&self) -> bool {
+        SERIAL::is_open(self)
+    }
+
+    fn writer(&self) -> Option<Box<dyn TransportWriter>> {
+        let port = self.port.as_ref()?;
+        clone_writer(&self.port_name, port.try_clone().map_err(io::Error::from))
+    }
+
+    // 드라이버에 따라 어댑터를 뽑아도 read 가 timeout 만 내므로 목록에서 확인
+    fn is_present(&self) -> bool {
+        !self.device.is_usb() || SERIAL::find_port(&self.device).is_some()
+    }
+
+    fn can_reopen(&self) -> bool {
+        true
+    }
+
+    fn reopen(&mut self) -> Result<(), SerialError> {
+        // USB 가 아닌 포트는 목록에 없을 수 있으므로 같은 경로로 다시 연다
+        let device = if self.device.is_usb() {
+            SERIAL::find_port(&self.device).ok_or(SerialError::Disconnected)?
         } else {
-            panic!("Serial port not initialized");
-        }
+            self.device.clone()
+        };
+        let config = self.config;
+
+        self.open_device(&device, &config)
     }
 }
@@ -0,0 +1,340 @@
+use log::{debug, error, info, trace, warn};
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::packet_log::{Direction, LogEntry};
+use crate::protocol::{Decoder, DecoderConfig, DecoderStats};
+use crate::scheduler::{Schedule, Scheduler};
+use crate::serial::SerialError;
+use crate::share::{ShareHub, ShareServer};
+use crate::transport::{SharedTransport, Transport};
+
+// 포트가 끊긴 뒤 다시 나타났는지 확인하는 간격
+const RECONNECT_POLL: Duration = Duration::from_millis(500);
+// 수신이 없을 때 연결이 살아 있는지 (USB 포트가 목록에 있는지 등) 확인하는 간격
+const HOTPLUG_POLL: Duration = Duration::from_secs(1);
+
+/// 수신 쓰레드가 UI 로 보내는 알림
+#[derive(Debug)]
+pub enum SessionEvent {
+    /// 읽기 오류로 수신 쓰레드가 끝남. 포트는 닫힌 상태
+    Disconnected(SerialError),
+    /// 포트가 끊겼고 auto-reconnect 로 다시 나타나기를 기다리는 중
+    Lost(SerialError),
+    /// 같은 장치를 찾아 다시 열었음. 값은 새 연결 이름
+    Reconnected(String),
+    /// 수신한 프레임과 반복 전송으로 보낸 프레임. CS 가 맞지 않는 프레임도 상태와 함께 온다
+    Packet(LogEntry),
+    /// 반복 전송 중 송신 오류로 멈춤
+    SendFailed(SerialError),
+    /// 반복 전송이 끝남. 값은 보낸 수
+    SendDone(u32),
+}
+
+/// 열린 연결 하나와 그 연결의 수신 쓰레드.
+/// drop 되면 수신 쓰레드를 멈추고 연결을 닫는다.
+pub struct Session {
+    // 수신 쓰레드가 transport 를 잡고 있는 동안에도 UI 에서 바로 읽을 수 있게 따로 보관
+    name: String,
+    lost: bool,
+    transport: Arc<SharedTransport>,
+    decoder_config: Arc<Mutex<DecoderConfig>>,
+    // 수신 쓰레드가 read 마다 갱신하는 디코더 통계
+    decoder_stats: Arc<Mutex<DecoderStats>>,
+    running: Arc<AtomicBool>,
+    auto_reconnect: Arc<AtomicBool>,
+    read_thread: Option<thread::JoinHandle<()>>,
+    events: mpsc::Receiver<SessionEvent>,
+    event_tx: mpsc::Sender<SessionEvent>,
+    scheduler: Option<Scheduler>,
+    // 수신한 바이트를 TCP client 들에게 나눠준다. server 가 없으면 client 도 없다
+    share_hub: ShareHub,
+    share: Option<ShareServer>,
+}
+
+impl Session {
+    /// 이미 열린 `transport` 로 세션을 시작한다.
+    /// 끊기면 auto-reconnect 가 켜져 있는 동안 `Transport::reopen` 을 반복한다
+    pub fn open(
+        transport: Box<dyn Transport>,
+        decoder_config: Arc<Mutex<DecoderConfig>>,
+        auto_reconnect: bool,
+    ) -> Session {
+        let name = transport.name();
+        info!("Port opened : {}", name);
+
+        let transport = Arc::new(SharedTransport::new(transport));
+        let running = Arc::new(AtomicBool::new(true));
+        let auto_reconnect = Arc::new(AtomicBool::new(auto_reconnect));
+        let (event_tx, events) = mpsc::channel();
+        let share_hub = ShareHub::new();
+        let decoder_stats = Arc::new(Mutex::new(DecoderStats::default()));
+        let read_thread = spawn_read_thread(
+            Arc::clone(&transport),
+            Arc::clone(&running),
+            Arc::clone(&auto_reconnect),
+            Arc::clone(&decoder_config),
+            Arc::clone(&decoder_stats),
+            event_tx.clone(),
+            share_hub.clone(),
+        );
+
+        Session {
+            name,
+            lost: false,
+            transport,
+            decoder_config,
+            decoder_stats,
+            running,
+            auto_reconnect,
+            read_thread: Some(read_thread),
+            events,
+            event_tx,
+            scheduler: None,
+            share_hub,
+            share: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 포트가 끊겨서 다시 연결되기를 기다리는 중
+    pub fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    pub fn set_auto_reconnect(&self, auto_reconnect: bool) {
+        self.auto_reconnect.store(auto_reconnect, Ordering::Relaxed);
+    }
+
+    /// 건너뛴 바이트, resync 횟수 등. 재연결해도 이어서 센다
+    pub fn decoder_stats(&self) -> DecoderStats {
+        *self.decoder_stats.lock().unwrap()
+    }
+
+    pub fn write(&self, data: &[u8]) -> Result<(), SerialError> {
+        self.transport.write(data)
+    }
+
+    /// 같은 프레임을 반복해서 보낸다. 이미 반복 중이면 먼저 멈춘다
+    pub fn start_schedule(&mut self, schedule: Schedule) {
+        self.stop_schedule();
+        self.scheduler = Some(Scheduler::start(
+            schedule,
+            Arc::clone(&self.transport),
+            self.event_tx.clone(),
+        ));
+    }
+
+    pub fn stop_schedule(&mut self) {
+        if let Some(scheduler) = self.scheduler.take() {
+            scheduler.stop();
+        }
+    }
+
+    /// 반복 전송 중이면 보낸 수와 전체 수 (None 이면 멈출 때까지)
+    pub fn schedule_progress(&self) -> Option<(u32, Option<u32>)> {
+        self.scheduler
+            .as_ref()
+            .map(|scheduler| (scheduler.sent(), scheduler.count()))
+    }
+
+    /// 이 연결을 `address` 의 TCP server 로 공유한다. 이미 공유 중이면 먼저 닫는다
+    pub fn start_share(&mut self, address: &str) -> io::Result<()> {
+        self.stop_share();
+        self.share = Some(ShareServer::start(
+            address,
+            self.share_hub.clone(),
+            Arc::clone(&self.transport),
+            Arc::clone(&self.decoder_config),
+            self.event_tx.clone(),
+        )?);
+
+        Ok(())
+    }
+
+    pub fn stop_share(&mut self) {
+        if let Some(share) = self.share.take() {
+            share.stop();
+        }
+    }
+
+    /// 공유 중이면 listen 주소와 접속한 client 수
+    pub fn share_status(&self) -> Option<(SocketAddr, usize)> {
+        self.share
+            .as_ref()
+            .map(|share| (share.address(), share.client_count()))
+    }
+
+    /// 지난 호출 이후 쌓인 알림. UI 가 매 프레임 호출한다
+    pub fn poll_events(&mut self) -> Vec<SessionEvent> {
+        let events: Vec<SessionEvent> = self.events.try_iter().collect();
+        for event in &events {
+            match event {
+                SessionEvent::Lost(_) => self.lost = true,
+                SessionEvent::Reconnected(name) => {
+                    self.lost = false;
+                    self.name = name.clone();
+                }
+                SessionEvent::Disconnected(_)
+                | SessionEvent::Packet(_)
+                | SessionEvent::SendFailed(_)
+                | SessionEvent::SendDone(_) => {}
+            }
+        }
+        // 다 보낸 반복 전송은 정리한다
+        if self
+            .scheduler
+            .as_ref()
+            .is_some_and(|scheduler| !scheduler.is_running())
+        {
+            self.scheduler = None;
+        }
+
+        events
+    }
+
+    pub fn close(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.stop_schedule();
+        self.stop_share();
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(read_thread) = self.read_thread.take() {
+            // read 는 READ_TIMEOUT 마다 돌아오므로 오래 기다리지 않는다
+            let _ = read_thread.join();
+        }
+
+        if self.transport.lock().is_open() {
+            self.transport.close();
+            info!("Port closed : {}", self.name);
+        }
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+fn spawn_read_thread(
+    transport: Arc<SharedTransport>,
+    running: Arc<AtomicBool>,
+    auto_reconnect: Arc<AtomicBool>,
+    decoder_config: Arc<Mutex<DecoderConfig>>,
+    decoder_stats: Arc<Mutex<DecoderStats>>,
+    event_tx: mpsc::Sender<SessionEvent>,
+    share_hub: ShareHub,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut decoder = Decoder::new();
+        let mut last_hotplug_check = Instant::now();
+
+        while running.load(Ordering::Relaxed) {
+            let result = transport.read();
+
+            let lost = match result {
+                Ok(bytes) if bytes.is_empty() => {
+                    if last_hotplug_check.elapsed() >= HOTPLUG_POLL {
+                        last_hotplug_check = Instant::now();
+                        if !transport.lock().is_present() {
+                            Some(SerialError::Disconnected)
+                        } else {
+                            None
+                        }
+                    } else {
+                        None
+                    }
+                }
+                Ok(bytes) => {
+                    trace!("Serial receive : {:02X?} ", bytes);
+                    share_hub.broadcast(&bytes);
+                    let config = decoder_config.lock().unwrap().clone();
+                    decoder.configure(&config);
+                    for result in decoder.feed(&bytes) {
+                        match result {
+                            Ok(p) => {
+                                debug!("Packet Received\r\n{}", p.to_string());
+                                let entry = LogEntry::from_packet(Direction::Rx, &p);
+                                let _ = event_tx.send(SessionEvent::Packet(entry));
+                            }
+                            Err(e) => {
+                                warn!("Packet dropped : {}", e);
+                                if let Some(entry) =
+                                    LogEntry::from_error(Direction::Rx, &config.spec, &e)
+                                {
+                                    let _ = event_tx.send(SessionEvent::Packet(entry));
+                                }
+                            }
+                        }
+                    }
+                    *decoder_stats.lock().unwrap() = *decoder.stats();
+                    None
+                }
+                Err(e) => Some(e),
+            };
+
+            let Some(e) = lost else {
+                continue;
+            };
+
+            error!("Serial read failed : {}", e);
+            transport.close();
+            let can_reopen = transport.lock().can_reopen();
+            if !can_reopen || !auto_reconnect.load(Ordering::Relaxed) {
+                let _ = event_tx.send(SessionEvent::Disconnected(e));
+                break;
+            }
+
+            let _ = event_tx.send(SessionEvent::Lost(e));
+            match wait_for_reopen(&transport, &running, &auto_reconnect) {
+                Some(name) => {
+                    // 끊기기 전에 조립 중이던 프레임은 버린다
+                    decoder.reset();
+                    info!("---------- reconnected : {} ----------", name);
+                    let _ = event_tx.send(SessionEvent::Reconnected(name));
+                }
+                None => {
+                    if running.load(Ordering::Relaxed) {
+                        let _ =
+                            event_tx.send(SessionEvent::Disconnected(SerialError::Disconnected));
+                    }
+                    break;
+                }
+            }
+        }
+    })
+}
+
+// 연결이 다시 열릴 때까지 `Transport::reopen` 을 반복한다.
+// 세션이 닫히거나 auto-reconnect 가 꺼지면 None
+fn wait_for_reopen(
+    transport: &SharedTransport,
+    running: &AtomicBool,
+    auto_reconnect: &AtomicBool,
+) -> Option<String> {
+    while running.load(Ordering::Relaxed) && auto_reconnect.load(Ordering::Relaxed) {
+        thread::sleep(RECONNECT_POLL);
+
+        match transport.reopen() {
+            Ok(name) => return Some(name),
+            // 장치가 아직 없거나 막 붙은 직후에는 열리지 않을 수 있다
+            Err(e) => debug!("Reconnect to {} failed : {}", transport.lock().name(), e),
+        }
+    }
+
+    None
+}
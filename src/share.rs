@@ -0,0 +1,312 @@
+use log::{debug, info, warn};
+use std::{
+    io::{self, Read, Write},
+    net::{Shutdown, SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+use crate::packet_log::{Direction, LogEntry};
+use crate::protocol::{Decoder, DecoderConfig};
+use crate::session::SessionEvent;
+use crate::transport::{SharedTransport, READ_BUF_SIZE, READ_TIMEOUT};
+
+// 멈춘 client 의 송신 쓰레드가 오래 붙잡혀 있지 않도록
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+// client 마다 쌓아 둘 수 있는 read 묶음 수. 넘치면 따라오지 못하는 client 로 보고 끊는다
+const QUEUE_DEPTH: usize = 256;
+
+struct ShareClient {
+    id: u64,
+    peer: SocketAddr,
+    // client 의 송신 쓰레드로 넘기는 큐. 수신 쓰레드는 여기에 넣기만 한다
+    queue: mpsc::SyncSender<Vec<u8>>,
+    stream: TcpStream,
+}
+
+/// 수신 쓰레드가 받은 바이트를 접속한 client 들에게 나눠준다.
+/// 가장 먼저 접속한 client 만 송신할 수 있고, 그 client 가 나가면 다음 client 가 이어받는다
+#[derive(Clone, Default)]
+pub struct ShareHub {
+    clients: Arc<Mutex<Vec<ShareClient>>>,
+}
+
+impl ShareHub {
+    pub fn new() -> ShareHub {
+        ShareHub::default()
+    }
+
+    /// 모든 client 의 큐에 넣는다. 기다리지 않으므로 느린 client 가 있어도 수신 쓰레드는 멈추지 않는다.
+    /// 큐가 가득 찬 client 는 끊는다
+    pub fn broadcast(&self, bytes: &[u8]) {
+        let mut clients = self.clients.lock().unwrap();
+        clients.retain(|client| match client.queue.try_send(bytes.to_vec()) {
+            Ok(()) => true,
+            Err(mpsc::TrySendError::Full(_)) => {
+                warn!("Share client {} dropped : falling behind", client.peer);
+                let _ = client.stream.shutdown(Shutdown::Both);
+                false
+            }
+            // 송신 쓰레드가 이미 끝남
+            Err(mpsc::TrySendError::Disconnected(_)) => false,
+        });
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    fn add(&self, client: ShareClient) {
+        self.clients.lock().unwrap().push(client);
+    }
+
+    fn remove(&self, id: u64) {
+        self.clients
+            .lock()
+            .unwrap()
+            .retain(|client| client.id != id);
+    }
+
+    fn is_writer(&self, id: u64) -> bool {
+        self.clients
+            .lock()
+            .unwrap()
+            .first()
+            .is_some_and(|client| client.id == id)
+    }
+
+    fn clear(&self) {
+        for client in self.clients.lock().unwrap().drain(..) {
+            let _ = client.stream.shutdown(Shutdown::Both);
+        }
+    }
+}
+
+/// 열린 연결을 TCP 로 공유하는 server. drop 되면 listener 와 client 를 모두 닫는다
+pub struct ShareServer {
+    address: SocketAddr,
+    hub: ShareHub,
+    running: Arc<AtomicBool>,
+    accept_thread: Option<thread::JoinHandle<()>>,
+}
+
+impl ShareServer {
+    /// `address` 에서 접속을 받는다. writer client 가 보낸 바이트는 `transport` 로 송신하고,
+    /// 그 안의 프레임은 `event_tx` 로 TX 행을 보낸다
+    pub fn start(
+        address: &str,
+        hub: ShareHub,
+        transport: Arc<SharedTransport>,
+        decoder_config: Arc<Mutex<DecoderConfig>>,
+        event_tx: mpsc::Sender<SessionEvent>,
+    ) -> io::Result<ShareServer> {
+        let listener = TcpListener::bind(address)?;
+        // accept 중에도 종료 요청을 확인할 수 있게
+        listener.set_nonblocking(true)?;
+        let address = listener.local_addr()?;
+        info!("Share server listening : {}", address);
+
+        let running = Arc::new(AtomicBool::new(true));
+        let accept_thread = spawn_accept_thread(
+            listener,
+            hub.clone(),
+            Arc::clone(&running),
+            Writer {
+                transport,
+                decoder_config,
+                event_tx,
+            },
+        );
+
+        Ok(ShareServer {
+            address,
+            hub,
+            running,
+            accept_thread: Some(accept_thread),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    pub fn client_count(&self) -> usize {
+        self.hub.client_count()
+    }
+
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(accept_thread) = self.accept_thread.take() {
+            let _ = accept_thread.join();
+        }
+        self.hub.clear();
+        info!("Share server stopped : {}", self.address);
+    }
+}
+
+impl Drop for ShareServer {
+    fn drop(&mut self) {
+        if self.accept_thread.is_some() {
+            self.shutdown();
+        }
+    }
+}
+
+// writer client 의 바이트를 보내는 곳과 로그에 남기는 곳
+#[derive(Clone)]
+struct Writer {
+    transport: Arc<SharedTransport>,
+    decoder_config: Arc<Mutex<DecoderConfig>>,
+    event_tx: mpsc::Sender<SessionEvent>,
+}
+
+fn spawn_accept_thread(
+    listener: TcpListener,
+    hub: ShareHub,
+    running: Arc<AtomicBool>,
+    writer: Writer,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut next_id = 0;
+
+        while running.load(Ordering::Relaxed) {
+            let (stream, peer) = match listener.accept() {
+                Ok(accepted) => accepted,
+                Err(ref e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(READ_TIMEOUT);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Share server accept failed : {}", e);
+                    continue;
+                }
+            };
+
+            let id = next_id;
+            next_id += 1;
+            let (client, reader) = match accept_client(id, peer, stream) {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("Share client {} rejected : {}", peer, e);
+                    continue;
+                }
+            };
+
+            hub.add(client);
+            info!(
+                "Share client connected : {} ({})",
+                peer,
+                if hub.is_writer(id) {
+                    "writer"
+                } else {
+                    "read-only"
+                }
+            );
+            spawn_client_thread(
+                id,
+                peer,
+                reader,
+                hub.clone(),
+                Arc::clone(&running),
+                writer.clone(),
+            );
+        }
+    })
+}
+
+// client 와 그 client 의 수신용 stream. 송신 쓰레드도 여기서 시작한다
+fn accept_client(
+    id: u64,
+    peer: SocketAddr,
+    stream: TcpStream,
+) -> io::Result<(ShareClient, TcpStream)> {
+    // listener 의 non-blocking 이 상속되는 플랫폼이 있다
+    stream.set_nonblocking(false)?;
+    stream.set_nodelay(true)?;
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let reader = stream.try_clone()?;
+    let sender = stream.try_clone()?;
+
+    let (queue, rx) = mpsc::sync_channel(QUEUE_DEPTH);
+    spawn_send_thread(peer, sender, rx);
+
+    Ok((
+        ShareClient {
+            id,
+            peer,
+            queue,
+            stream,
+        },
+        reader,
+    ))
+}
+
+// 큐에 쌓인 수신 바이트를 client 로 보낸다. hub 에서 빠지면 (큐가 닫히면) 끝난다
+fn spawn_send_thread(peer: SocketAddr, mut stream: TcpStream, rx: mpsc::Receiver<Vec<u8>>) {
+    thread::spawn(move || {
+        for bytes in rx {
+            if let Err(e) = stream.write_all(&bytes) {
+                warn!("Share client {} dropped : {}", peer, e);
+                // 수신 쪽 쓰레드도 끝나서 hub 에서 빠진다
+                let _ = stream.shutdown(Shutdown::Both);
+                break;
+            }
+        }
+    });
+}
+
+// client 가 보낸 바이트를 받는다. writer 가 아니면 버린다
+fn spawn_client_thread(
+    id: u64,
+    peer: SocketAddr,
+    mut stream: TcpStream,
+    hub: ShareHub,
+    running: Arc<AtomicBool>,
+    writer: Writer,
+) {
+    thread::spawn(move || {
+        let mut buf = [0; READ_BUF_SIZE];
+        // client 가 프레임을 나눠 보내도 로그에는 프레임 단위로 남긴다
+        let mut decoder = Decoder::new();
+
+        while running.load(Ordering::Relaxed) {
+            let n = match stream.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(ref e)
+                    if e.kind() == io::ErrorKind::TimedOut
+                        || e.kind() == io::ErrorKind::WouldBlock =>
+                {
+                    continue;
+                }
+                Err(_) => break,
+            };
+
+            if !hub.is_writer(id) {
+                debug!("Share client {} is read-only, {} bytes ignored", peer, n);
+                continue;
+            }
+            if let Err(e) = writer.transport.write(&buf[..n]) {
+                warn!("Share client {} write failed : {}", peer, e);
+                continue;
+            }
+            decoder.configure(&writer.decoder_config.lock().unwrap());
+            for packet in decoder.feed(&buf[..n]).flatten() {
+                let entry = LogEntry::from_packet(Direction::Tx, &packet);
+                let _ = writer.event_tx.send(SessionEvent::Packet(entry));
+            }
+        }
+
+        hub.remove(id);
+        info!("Share client disconnected : {}", peer);
+    });
+}
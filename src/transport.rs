@@ -0,0 +1,470 @@
+use std::{
+    fmt, fs,
+    io::{self, Read, Write},
+    net::{TcpStream, ToSocketAddrs},
+    path::Path,
+    sync::{mpsc, Mutex, MutexGuard, PoisonError},
+    thread,
+    time::Duration,
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+use crate::serial::SerialError;
+
+pub(crate) const READ_BUF_SIZE: usize = 1024;
+// read 가 데이터 없이 돌아오는 시간. 수신 쓰레드가 이 간격으로 종료 요청을 확인한다
+pub(crate) const READ_TIMEOUT: Duration = Duration::from_millis(100);
+// 응답 없는 주소에 접속할 때 OS 기본 timeout (수십 초) 까지 기다리지 않는다
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 수신 쓰레드와 GUI 가 쓰는 바이트 스트림.
+/// `read` 는 READ_TIMEOUT 정도 기다려서 수신이 없으면 빈 Vec 을 돌려줘야 한다.
+pub trait Transport: Send {
+    /// 상태 표시에 쓰는 이름. 포트 이름, 주소 등
+    fn name(&self) -> String;
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError>;
+
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError>;
+
+    fn close(&mut self);
+
+    fn is_open(&self) -> bool;
+
+    /// read 와 동시에 쓸 수 있는 송신 핸들. (`try_clone` 한 포트, 소켓 등)
+    /// None 이면 read 사이사이에 `write` 로 보낸다
+    fn writer(&self) -> Option<Box<dyn TransportWriter>> {
+        None
+    }
+
+    /// 수신이 없을 때 연결이 아직 살아 있는지 확인. (USB 포트가 목록에서 사라졌는지 등)
+    fn is_present(&self) -> bool {
+        true
+    }
+
+    /// 끊겼을 때 `reopen` 으로 다시 열 수 있는 연결인지
+    fn can_reopen(&self) -> bool {
+        false
+    }
+
+    /// 끊긴 연결을 같은 설정으로 다시 연다
+    fn reopen(&mut self) -> Result<(), SerialError> {
+        Err(SerialError::NotOpen)
+    }
+}
+
+/// `Transport::writer` 가 돌려주는 송신 전용 핸들
+pub trait TransportWriter: Send {
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError>;
+}
+
+impl TransportWriter for TcpStream {
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        self.write_all(data)?;
+
+        Ok(())
+    }
+}
+
+// Loopback 의 송신 쪽
+impl TransportWriter for mpsc::Sender<Vec<u8>> {
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        self.send(data.to_vec())
+            .map_err(|_| SerialError::Disconnected)
+    }
+}
+
+// 송신을 버리는 연결 (Replay)
+impl TransportWriter for io::Sink {
+    fn write(&mut self, _data: &[u8]) -> Result<(), SerialError> {
+        Ok(())
+    }
+}
+
+// 다른 쓰레드가 lock 을 잡은 채 panic 해도 연결은 계속 쓴다
+fn lock<T: ?Sized>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 수신 쓰레드와 송신하는 쪽 (UI, 반복 전송, 공유 client) 이 함께 쓰는 연결.
+/// 송신은 따로 연 송신 핸들로 보내므로 진행 중인 read 를 기다리지 않는다.
+/// transport 와 writer 의 lock 은 동시에 잡지 않는다
+pub struct SharedTransport {
+    transport: Mutex<Box<dyn Transport>>,
+    // 열려 있는 동안의 송신 핸들. 없으면 transport 로 보낸다
+    writer: Mutex<Option<Box<dyn TransportWriter>>>,
+}
+
+impl SharedTransport {
+    pub fn new(transport: Box<dyn Transport>) -> SharedTransport {
+        let writer = transport.writer();
+        SharedTransport {
+            transport: Mutex::new(transport),
+            writer: Mutex::new(writer),
+        }
+    }
+
+    pub fn write(&self, data: &[u8]) -> Result<(), SerialError> {
+        if let Some(writer) = lock(&self.writer).as_mut() {
+            return writer.write(data);
+        }
+        // 송신 핸들이 없으면 (끊김, 재연결 중 등) writer lock 을 놓은 뒤 transport 로 보낸다
+        lock(&self.transport).write(data)
+    }
+
+    /// 수신 쓰레드가 쓴다. READ_TIMEOUT 동안 transport 를 잡고 있다
+    pub fn read(&self) -> Result<Vec<u8>, SerialError> {
+        lock(&self.transport).read()
+    }
+
+    pub fn close(&self) {
+        *lock(&self.writer) = None;
+        lock(&self.transport).close();
+    }
+
+    /// 다시 열고 새 연결 이름을 돌려준다
+    pub fn reopen(&self) -> Result<String, SerialError> {
+        let (name, writer) = {
+            let mut transport = lock(&self.transport);
+            transport.reopen()?;
+            (transport.name(), transport.writer())
+        };
+        *lock(&self.writer) = writer;
+
+        Ok(name)
+    }
+
+    /// read, write 외의 작업 (상태 확인 등)
+    pub fn lock(&self) -> MutexGuard<'_, Box<dyn Transport>> {
+        lock(&self.transport)
+    }
+}
+
+// 송신 핸들을 만들지 못해도 read 사이에 보낼 수 있으므로 경고만 남긴다
+pub(crate) fn clone_writer<W: TransportWriter + 'static>(
+    name: &str,
+    clone: io::Result<W>,
+) -> Option<Box<dyn TransportWriter>> {
+    match clone {
+        Ok(writer) => Some(Box::new(writer)),
+        Err(e) => {
+            warn!("{} : cannot open separate writer : {}", name, e);
+            None
+        }
+    }
+}
+
+/// GUI 에서 고르는 연결 종류
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, EnumIter)]
+pub enum TransportKind {
+    Serial,
+    Tcp,
+    Rfc2217,
+    Replay,
+    Loopback,
+}
+
+impl fmt::Display for TransportKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            TransportKind::Serial => write!(f, "Serial"),
+            TransportKind::Tcp => write!(f, "TCP"),
+            TransportKind::Rfc2217 => write!(f, "RFC 2217"),
+            TransportKind::Replay => write!(f, "Replay"),
+            TransportKind::Loopback => write!(f, "Loopback"),
+        }
+    }
+}
+
+/// `host:port` 로 접속하는 TCP 클라이언트. 받은 바이트를 그대로 넘긴다
+pub struct TcpTransport {
+    address: String,
+    stream: Option<TcpStream>,
+    // server 에서 accept 한 연결은 다시 접속할 수 없다
+    accepted: bool,
+    buf: [u8; READ_BUF_SIZE],
+}
+
+impl TcpTransport {
+    pub fn connect(address: &str) -> Result<TcpTransport, SerialError> {
+        let mut transport = TcpTransport {
+            address: address.to_string(),
+            stream: None,
+            accepted: false,
+            buf: [0; READ_BUF_SIZE],
+        };
+        transport.reopen()?;
+
+        Ok(transport)
+    }
+
+    /// `TcpListener::accept` 로 받은 연결
+    pub fn from_stream(stream: TcpStream) -> Result<TcpTransport, SerialError> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+
+        Ok(TcpTransport {
+            address: stream.peer_addr()?.to_string(),
+            stream: Some(stream),
+            accepted: true,
+            buf: [0; READ_BUF_SIZE],
+        })
+    }
+
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    pub(crate) fn stream(&self) -> Option<&TcpStream> {
+        self.stream.as_ref()
+    }
+}
+
+impl Transport for TcpTransport {
+    fn name(&self) -> String {
+        format!("tcp://{}", self.address)
+    }
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        let stream = self.stream.as_mut().ok_or(SerialError::NotOpen)?;
+        match stream.read(&mut self.buf) {
+            Ok(0) => Err(SerialError::Disconnected),
+            Ok(n) => Ok(self.buf[..n].to_vec()),
+            // 플랫폼에 따라 read timeout 이 WouldBlock 으로 온다
+            Err(ref e)
+                if e.kind() == io::ErrorKind::TimedOut || e.kind() == io::ErrorKind::WouldBlock =>
+            {
+                Ok(Vec::new())
+            }
+            Err(e) => Err(SerialError::Io(e)),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        let stream = self.stream.as_mut().ok_or(SerialError::NotOpen)?;
+        stream.write_all(data)?;
+
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.stream = None;
+    }
+
+    fn is_open(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn writer(&self) -> Option<Box<dyn TransportWriter>> {
+        let stream = self.stream.as_ref()?;
+        clone_writer(&self.name(), stream.try_clone())
+    }
+
+    fn can_reopen(&self) -> bool {
+        !self.accepted
+    }
+
+    fn reopen(&mut self) -> Result<(), SerialError> {
+        let stream = connect_stream(&self.address)?;
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        stream.set_nodelay(true)?;
+        self.stream = Some(stream);
+
+        Ok(())
+    }
+}
+
+// 이름이 여러 주소로 풀리면 차례로 CONNECT_TIMEOUT 씩 시도한다
+fn connect_stream(address: &str) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for addr in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "address resolved to nothing")
+    }))
+}
+
+/// 저장해 둔 바이트열을 수신한 것처럼 돌려준다. 송신은 버린다.
+/// 끝까지 읽으면 Disconnected
+pub struct ReplayTransport {
+    name: String,
+    data: Vec<u8>,
+    pos: usize,
+    chunk: usize,
+    interval: Duration,
+    open: bool,
+}
+
+impl ReplayTransport {
+    pub fn open(path: impl AsRef<Path>) -> Result<ReplayTransport, SerialError> {
+        let path = path.as_ref();
+        let data = fs::read(path)?;
+        let mut transport = ReplayTransport::from_bytes(data);
+        transport.name = format!("replay {}", path.display());
+
+        Ok(transport)
+    }
+
+    pub fn from_bytes(data: Vec<u8>) -> ReplayTransport {
+        ReplayTransport {
+            name: String::from("replay"),
+            data,
+            pos: 0,
+            chunk: READ_BUF_SIZE,
+            interval: Duration::ZERO,
+            open: true,
+        }
+    }
+
+    /// read 한 번에 돌려주는 최대 바이트 수
+    pub fn with_chunk(mut self, chunk: usize) -> ReplayTransport {
+        self.chunk = chunk.max(1);
+        self
+    }
+
+    /// read 사이의 간격. 실제 장치처럼 천천히 흘려보낼 때 쓴다
+    pub fn with_interval(mut self, interval: Duration) -> ReplayTransport {
+        self.interval = interval;
+        self
+    }
+}
+
+impl Transport for ReplayTransport {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        if !self.open {
+            return Err(SerialError::NotOpen);
+        }
+        if self.pos >= self.data.len() {
+            return Err(SerialError::Disconnected);
+        }
+
+        if !self.interval.is_zero() {
+            thread::sleep(self.interval);
+        }
+        let end = (self.pos + self.chunk).min(self.data.len());
+        let bytes = self.data[self.pos..end].to_vec();
+        self.pos = end;
+
+        Ok(bytes)
+    }
+
+    fn write(&mut self, _data: &[u8]) -> Result<(), SerialError> {
+        if !self.open {
+            return Err(SerialError::NotOpen);
+        }
+
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+    }
+
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn writer(&self) -> Option<Box<dyn TransportWriter>> {
+        self.open
+            .then(|| Box::new(io::sink()) as Box<dyn TransportWriter>)
+    }
+}
+
+/// 메모리 안에서 주고받는 연결. `new` 는 보낸 것을 그대로 돌려받고,
+/// `pair` 는 서로 연결된 두 끝을 만든다.
+///
+/// ```
+/// use RUST_tutorial::protocol::{Decoder, PacketBuilder};
+/// use RUST_tutorial::transport::{Loopback, Transport};
+///
+/// let (mut device, mut host) = Loopback::pair();
+/// let frame = PacketBuilder::new().id(0xC1).command(0x12).payload(&[0x04, 0x78]);
+/// device.write(&frame.to_bytes().unwrap()).unwrap();
+///
+/// let bytes = host.read().unwrap();
+/// let packet = Decoder::new().feed(&bytes).next().unwrap().unwrap();
+/// assert_eq!(packet.payload(), [0x04, 0x78]);
+/// ```
+pub struct Loopback {
+    tx: Option<mpsc::Sender<Vec<u8>>>,
+    rx: Option<mpsc::Receiver<Vec<u8>>>,
+}
+
+impl Loopback {
+    pub fn new() -> Loopback {
+        let (tx, rx) = mpsc::channel();
+        Loopback {
+            tx: Some(tx),
+            rx: Some(rx),
+        }
+    }
+
+    pub fn pair() -> (Loopback, Loopback) {
+        let (a_tx, b_rx) = mpsc::channel();
+        let (b_tx, a_rx) = mpsc::channel();
+        (
+            Loopback {
+                tx: Some(a_tx),
+                rx: Some(a_rx),
+            },
+            Loopback {
+                tx: Some(b_tx),
+                rx: Some(b_rx),
+            },
+        )
+    }
+}
+
+impl Default for Loopback {
+    fn default() -> Self {
+        Loopback::new()
+    }
+}
+
+impl Transport for Loopback {
+    fn name(&self) -> String {
+        String::from("loopback")
+    }
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        let rx = self.rx.as_ref().ok_or(SerialError::NotOpen)?;
+        match rx.recv_timeout(READ_TIMEOUT) {
+            Ok(bytes) => Ok(bytes),
+            Err(mpsc::RecvTimeoutError::Timeout) => Ok(Vec::new()),
+            // 상대 쪽이 닫힘
+            Err(mpsc::RecvTimeoutError::Disconnected) => Err(SerialError::Disconnected),
+        }
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        let tx = self.tx.as_ref().ok_or(SerialError::NotOpen)?;
+        tx.send(data.to_vec())
+            .map_err(|_| SerialError::Disconnected)
+    }
+
+    fn close(&mut self) {
+        self.tx = None;
+        self.rx = None;
+    }
+
+    fn is_open(&self) -> bool {
+        self.rx.is_some()
+    }
+
+    fn writer(&self) -> Option<Box<dyn TransportWriter>> {
+        let tx = self.tx.as_ref()?.clone();
+        Some(Box::new(tx))
+    }
+}
@@ -0,0 +1,48 @@
+//! CS 알고리즘을 알려진 값과 비교한다. 왕복 시험만으로는 알고리즘이 틀려도 통과하기 때문이다.
+#![allow(non_snake_case)]
+
+use RUST_tutorial::protocol::{
+    Checksum, ChecksumKind, Crc16Modbus, Crc8, FrameSpec, PacketBuilder, Sum8, Xor8, XorIncrement,
+};
+
+// CRC 카탈로그의 표준 check 입력
+const CHECK: &[u8] = b"123456789";
+
+#[test]
+fn crc_check_values() {
+    assert_eq!(Crc8.compute(CHECK), 0xF4);
+    assert_eq!(Crc16Modbus.compute(CHECK), 0x4B37);
+    // 프레임에는 하위 바이트가 먼저 붙는다
+    assert_eq!(Crc16Modbus.encode(0x4B37), [0x37, 0x4B]);
+}
+
+#[test]
+fn simple_checksum_values() {
+    // 0x31 + .. + 0x39 = 0x1DD
+    assert_eq!(Sum8.compute(CHECK), 0xDD);
+    assert_eq!(Xor8.compute(CHECK), 0x31);
+    // DUMMY 프레임의 CS
+    assert_eq!(
+        XorIncrement.compute(&[0x02, 0xC1, 0x08, 0x12, 0x00, 0x04, 0x78]),
+        0x9F
+    );
+}
+
+#[test]
+fn crc16_trailer_is_little_endian() {
+    let spec = FrameSpec {
+        checksum: ChecksumKind::Crc16Modbus,
+        ..FrameSpec::default()
+    };
+    let bytes = PacketBuilder::new()
+        .spec(&spec)
+        .id(0xC1)
+        .command(0x12)
+        .payload(&[0x04])
+        .to_bytes()
+        .unwrap();
+
+    let (frame, trailer) = bytes.split_at(bytes.len() - 2);
+    let crc = Crc16Modbus.compute(frame);
+    assert_eq!(trailer, [crc as u8, (crc >> 8) as u8]);
+}
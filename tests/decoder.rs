@@ -0,0 +1,77 @@
+//! Decoder 의 timeout, resync 처리를 정해진 바이트열로 시험한다.
+#![allow(non_snake_case)]
+
+use std::time::{Duration, Instant};
+
+use RUST_tutorial::protocol::{Decoder, PacketBuilder, ParseError};
+
+const DUMMY: [u8; 8] = [0x02, 0xC1, 0x08, 0x12, 0x00, 0x04, 0x78, 0x9F];
+
+#[test]
+fn inter_byte_timeout_drops_partial_frame() {
+    let timeout = Duration::from_millis(50);
+    let mut decoder = Decoder::new();
+    decoder.set_timeout(Some(timeout));
+
+    // 간격이 timeout 안이면 나뉘어 들어와도 이어서 조립한다
+    let t0 = Instant::now();
+    assert_eq!(decoder.feed_at(&DUMMY[..4], t0).count(), 0);
+    let results: Vec<_> = decoder.feed_at(&DUMMY[4..], t0 + timeout).collect();
+    assert!(matches!(results[..], [Ok(_)]));
+
+    // 프레임 중간에 끊겼다가 다음 STX 가 늦게 오면 앞의 조각은 버리고 새 프레임을 받는다
+    let t0 = t0 + timeout;
+    assert_eq!(decoder.feed_at(&DUMMY[..4], t0).count(), 0);
+    let results: Vec<_> = decoder
+        .feed_at(&DUMMY, t0 + timeout + Duration::from_millis(1))
+        .collect();
+    assert!(matches!(
+        results[..],
+        [Err(ParseError::Timeout { discarded: 4 }), Ok(_)]
+    ));
+    assert_eq!(results[1].as_ref().unwrap().serialize(), DUMMY);
+
+    let stats = decoder.stats();
+    assert_eq!((stats.frames, stats.timeouts), (2, 1));
+}
+
+#[test]
+fn resync_recovers_frame_inside_corrupted_frame() {
+    // payload 에 DUMMY 가 통째로 들어간 프레임의 CS 를 깨고, 뒤에 멀쩡한 DUMMY 를 붙인다
+    let mut corrupted = PacketBuilder::new()
+        .id(0xC1)
+        .command(0x12)
+        .payload(&DUMMY)
+        .to_bytes()
+        .unwrap();
+    *corrupted.last_mut().unwrap() ^= 0xFF;
+    let input = [&corrupted[..], &DUMMY[..]].concat();
+
+    let mut decoder = Decoder::new();
+    decoder.set_resync(true);
+    let results: Vec<_> = decoder.feed(&input).collect();
+    assert!(matches!(
+        results[..],
+        [
+            Err(ParseError::ChecksumMismatch { .. }),
+            Err(ParseError::DiscardedBytes(4)),
+            Ok(_),
+            Err(ParseError::DiscardedBytes(1)),
+            Ok(_),
+        ]
+    ));
+    assert_eq!(results[2].as_ref().unwrap().serialize(), DUMMY);
+    assert_eq!(results[4].as_ref().unwrap().serialize(), DUMMY);
+
+    // 깨진 프레임의 헤더 4 바이트와 CS 1 바이트를 건너뛴다
+    let stats = decoder.stats();
+    assert_eq!(
+        (
+            stats.frames,
+            stats.checksum_errors,
+            stats.resyncs,
+            stats.skipped_bytes
+        ),
+        (2, 1, 1, 5)
+    );
+}
@@ -0,0 +1,120 @@
+//! 필터 문법과 필터링된 로그 화면을 시험한다.
+#![allow(non_snake_case)]
+
+use RUST_tutorial::filter::{ByteFilter, Expression, FilterError, PacketFilter};
+use RUST_tutorial::packet_log::{Direction, LogEntry, PacketLog};
+use RUST_tutorial::protocol::PacketBuilder;
+
+fn entry(id: u8, command: u8, payload: &[u8]) -> LogEntry {
+    let packet = PacketBuilder::new()
+        .id(id)
+        .command(command)
+        .payload(payload)
+        .build()
+        .unwrap();
+    LogEntry::from_packet(Direction::Rx, &packet)
+}
+
+#[test]
+fn byte_filter_lists_ranges_and_negation() {
+    let filter = ByteFilter::parse("C1 0x20-0x2f, !25").unwrap();
+    assert!(filter.matches(0xC1));
+    assert!(filter.matches(0x2F));
+    assert!(!filter.matches(0x25));
+    assert!(!filter.matches(0x30));
+
+    assert!(ByteFilter::parse("").unwrap().matches(0x00));
+    assert_eq!(
+        ByteFilter::parse("1F-10"),
+        Err(FilterError::InvalidItem(String::from("1F-10")))
+    );
+    assert!(ByteFilter::parse("C1, XY").is_err());
+}
+
+#[test]
+fn expression_matches_fields() {
+    let expression = Expression::parse("id == 0xC1 && data[0] > 0x10 && len > 8").unwrap();
+    // LEN = 헤더 5 + CS 1 + DATA
+    assert!(expression.matches(&entry(0xC1, 0x12, &[0x11, 0x00, 0x00])));
+    assert!(!expression.matches(&entry(0xC1, 0x12, &[0x10, 0x00, 0x00])));
+    assert!(!expression.matches(&entry(0xC1, 0x12, &[0x11, 0x00])));
+    assert!(!expression.matches(&entry(0xC2, 0x12, &[0x11, 0x00, 0x00])));
+}
+
+#[test]
+fn expression_precedence_and_missing_values() {
+    // && 가 || 보다 먼저 묶인다
+    let expression = Expression::parse("cmd == 1 || cmd == 2 && data.len > 0").unwrap();
+    assert!(expression.matches(&entry(0, 1, &[])));
+    assert!(!expression.matches(&entry(0, 2, &[])));
+    assert!(expression.matches(&entry(0, 2, &[0xFF])));
+
+    // 여러 줄로 붙여 넣은 식
+    let expression = Expression::parse("cmd == 1\r\n||\u{a0}cmd == 2").unwrap();
+    assert!(expression.matches(&entry(0, 2, &[])));
+
+    let expression = Expression::parse("!(data[4] == 0)").unwrap();
+    // DATA 범위 밖과의 비교는 거짓이므로 부정하면 참
+    assert!(expression.matches(&entry(0, 0, &[])));
+}
+
+#[test]
+fn expression_errors_point_at_the_problem() {
+    match Expression::parse("id == 0xC1 && foo > 1") {
+        Err(FilterError::Syntax { position, .. }) => assert_eq!(position, 14),
+        other => panic!("expected syntax error, got {:?}", other),
+    }
+    assert!(Expression::parse("id ==").is_err());
+    assert!(Expression::parse("(id == 1").is_err());
+    assert!(Expression::parse("id == 1 2").is_err());
+    assert!(Expression::parse("data[x] == 1").is_err());
+}
+
+#[test]
+fn filtered_log_follows_filter_and_capacity() {
+    let mut log = PacketLog::with_capacity(4);
+    for id in 0..6u8 {
+        log.push(entry(id, 0x10 + id % 2, &[]));
+    }
+    // 오래된 두 행은 지워졌다
+    assert_eq!(log.len(), 4);
+
+    log.set_filter(PacketFilter::parse("", "11", "").unwrap());
+    let ids: Vec<u8> = log.iter_visible().map(|entry| entry.id).collect();
+    assert_eq!(ids, [3, 5]);
+
+    log.push(entry(6, 0x11, &[]));
+    log.push(entry(7, 0x11, &[]));
+    let ids: Vec<u8> = log.iter_visible().map(|entry| entry.id).collect();
+    assert_eq!(ids, [5, 6, 7]);
+    assert_eq!(log.visible(0).unwrap().id, 5);
+
+    log.set_filter(PacketFilter::default());
+    assert_eq!(log.visible_len(), 4);
+}
+
+#[test]
+fn markers_pass_filters_and_reach_csv() {
+    let mut log = PacketLog::new();
+    log.set_filter(PacketFilter::parse("C1", "", "").unwrap());
+    log.push(entry(0xC1, 0x10, &[0x01]));
+    log.push(LogEntry::marker("reconnected : /dev/ttyUSB0"));
+    log.push(entry(0xC2, 0x10, &[]));
+    // OS 오류 문구에는 `,` `"` 줄바꿈이 들어 있을 수 있다
+    log.push(LogEntry::marker("lost : COM3 : \"busy\", retry\nlater"));
+
+    assert_eq!(log.visible_len(), 3);
+    assert!(log.visible(1).unwrap().note.is_some());
+
+    let dir = std::env::temp_dir().join(format!("filter_markers_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("log.csv");
+    assert_eq!(log.save_csv(&path).unwrap(), 3);
+    let csv = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows.len(), 5);
+    assert!(rows[2].ends_with(",,,,,,,,reconnected : /dev/ttyUSB0"));
+    assert!(csv.ends_with(",,,,,,,,\"lost : COM3 : \"\"busy\"\", retry\nlater\"\n"));
+}
@@ -0,0 +1,126 @@
+//! 명령 목록의 자리표시자, 파일 형식, 단축키를 시험한다.
+#![allow(non_snake_case)]
+
+use RUST_tutorial::library::{parse_shortcut, Command, Library, LibraryError};
+use RUST_tutorial::protocol::{FrameSpec, PacketBuilder};
+
+fn command(name: &str, group: &str, payload: &str) -> Command {
+    Command {
+        name: String::from(name),
+        group: String::from(group),
+        id: String::from("C1"),
+        command: String::from("20"),
+        payload: String::from(payload),
+        shortcut: String::from("Ctrl+1"),
+    }
+}
+
+#[test]
+fn placeholders_expand_to_bytes() {
+    let command = command("ping", "", "AA{seq}{u8:255} {u32:0x01020304}BB");
+    assert_eq!(
+        command.expand_payload(0x05).unwrap(),
+        [0xAA, 0x05, 0xFF, 0x01, 0x02, 0x03, 0x04, 0xBB]
+    );
+    assert_eq!(
+        self::command("t", "", "{time}")
+            .expand_payload(0)
+            .unwrap()
+            .len(),
+        4
+    );
+
+    for payload in ["{u8:256}", "{foo}", "01 {seq", "012{seq}"] {
+        assert!(matches!(
+            self::command("bad", "", payload).expand_payload(0),
+            Err(LibraryError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn build_matches_packet_builder() {
+    let spec = FrameSpec::default();
+    let packet = command("ping", "", "01 {seq}").build(&spec, 0x10).unwrap();
+    let expected = PacketBuilder::new()
+        .spec(&spec)
+        .id(0xC1)
+        .command(0x20)
+        .sequence(0x10)
+        .payload(&[0x01, 0x10])
+        .build()
+        .unwrap();
+    assert_eq!(packet.to_wire(), expected.to_wire());
+
+    let mut bad = command("bad", "", "");
+    bad.id = String::from("C1C2");
+    assert!(bad.build(&spec, 0).is_err());
+}
+
+#[test]
+fn json_and_toml_round_trip() {
+    let library = Library {
+        commands: vec![
+            command("ping", "basic", "01"),
+            command("reset", "maintenance", "{u16:1000}"),
+        ],
+    };
+
+    assert_eq!(
+        Library::from_json(&library.to_json().unwrap()).unwrap(),
+        library
+    );
+    assert_eq!(
+        Library::from_toml(&library.to_toml().unwrap()).unwrap(),
+        library
+    );
+
+    // 동시에 도는 다른 시험과 겹치지 않도록 프로세스와 시험 이름으로 디렉터리를 나눈다
+    let dir = std::env::temp_dir().join(format!(
+        "library_json_and_toml_round_trip_{}",
+        std::process::id()
+    ));
+    std::fs::create_dir_all(&dir).unwrap();
+    for name in ["commands.json", "commands.toml"] {
+        let path = dir.join(name);
+        library.save(&path).unwrap();
+        assert_eq!(Library::load(&path).unwrap(), library);
+    }
+    assert!(matches!(
+        library.save(dir.join("commands.txt")),
+        Err(LibraryError::UnknownFormat(_))
+    ));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn merge_replaces_same_name_in_group() {
+    let mut library = Library {
+        commands: vec![command("ping", "a", "01"), command("ping", "b", "02")],
+    };
+    library.merge(Library {
+        commands: vec![command("ping", "a", "03"), command("stop", "c", "04")],
+    });
+
+    let payloads: Vec<&str> = library
+        .commands
+        .iter()
+        .map(|c| c.payload.as_str())
+        .collect();
+    assert_eq!(payloads, ["03", "02", "04"]);
+    assert_eq!(library.groups(), ["a", "b", "c"]);
+}
+
+#[test]
+fn shortcuts_parse() {
+    let shortcut = parse_shortcut("Ctrl+Shift+1").unwrap();
+    assert_eq!(shortcut.logical_key, egui::Key::Num1);
+    assert_eq!(
+        shortcut.modifiers,
+        egui::Modifiers::CTRL | egui::Modifiers::SHIFT
+    );
+    assert_eq!(parse_shortcut("F5").unwrap().logical_key, egui::Key::F5);
+    assert!(parse_shortcut("").is_none());
+    assert!(parse_shortcut("Ctrl").is_none());
+    assert!(parse_shortcut("Ctrl+A+B").is_none());
+}
@@ -0,0 +1,205 @@
+//! PACKET / Decoder 를 임의 입력으로 시험한다.
+//! 만든 프레임은 그대로 다시 파싱되어야 하고, 어떤 바이트가 들어와도 panic 이 나면 안 된다.
+#![allow(non_snake_case)]
+
+use proptest::prelude::*;
+use strum::IntoEnumIterator;
+
+use RUST_tutorial::protocol::{
+    ChecksumKind, Decoder, Field, FrameSpec, Framing, PacketBuilder, ParseError, ParseEvent, PACKET,
+};
+
+// 시험 한 건에 너무 오래 걸리지 않도록
+const MAX_TEST_PAYLOAD: usize = 512;
+
+fn specs() -> Vec<FrameSpec> {
+    let aa55 = FrameSpec {
+        name: String::from("AA55 family"),
+        sync: vec![0xAA, 0x55],
+        fields: vec![Field::Id, Field::Length, Field::Command],
+        length_width: 2,
+        length_includes_overhead: false,
+        checksum: ChecksumKind::Crc16Modbus,
+        framing: Framing::None,
+    };
+
+    let mut specs = Vec::new();
+    for base in [FrameSpec::default(), FrameSpec::extended(), aa55] {
+        for checksum in ChecksumKind::iter() {
+            for framing in Framing::iter() {
+                specs.push(FrameSpec {
+                    checksum,
+                    framing,
+                    ..base.clone()
+                });
+            }
+        }
+    }
+    specs
+}
+
+fn spec_strategy() -> impl Strategy<Value = FrameSpec> {
+    prop::sample::select(specs())
+}
+
+fn frame_strategy() -> impl Strategy<Value = (FrameSpec, u8, u8, u8, Vec<u8>)> {
+    spec_strategy().prop_flat_map(|spec| {
+        let max = spec.max_payload().min(MAX_TEST_PAYLOAD);
+        (
+            Just(spec),
+            any::<u8>(),
+            any::<u8>(),
+            any::<u8>(),
+            prop::collection::vec(any::<u8>(), 0..=max),
+        )
+    })
+}
+
+fn decoder(spec: &FrameSpec, resync: bool) -> Decoder {
+    let mut decoder = Decoder::new();
+    decoder.set_spec(spec);
+    decoder.set_resync(resync);
+    decoder
+}
+
+proptest! {
+    #[test]
+    fn built_frame_round_trips((spec, id, command, sequence, payload) in frame_strategy()) {
+        let packet = PacketBuilder::new()
+            .spec(&spec)
+            .id(id)
+            .command(command)
+            .sequence(sequence)
+            .payload(&payload)
+            .build()
+            .unwrap();
+
+        let mut decoder = decoder(&spec, false);
+        let results: Vec<_> = decoder.feed(&packet.to_wire()).collect();
+
+        prop_assert_eq!(results.len(), 1, "{:?}", results);
+        let decoded = results[0].as_ref().unwrap();
+        prop_assert_eq!(decoded.header.id, id);
+        prop_assert_eq!(decoded.header.command, command);
+        if spec.fields.contains(&Field::Sequence) {
+            prop_assert_eq!(decoded.header.sequence, sequence);
+        }
+        prop_assert_eq!(decoded.header.length, packet.header.length);
+        prop_assert_eq!(decoded.payload(), &payload[..]);
+        prop_assert_eq!(decoded.checksum, packet.checksum);
+        prop_assert_eq!(decoded.serialize(), packet.serialize());
+    }
+
+    #[test]
+    fn frame_split_at_any_point_is_reassembled(
+        (spec, id, command, sequence, payload) in frame_strategy(),
+        split in any::<prop::sample::Index>(),
+    ) {
+        let wire = PacketBuilder::new()
+            .spec(&spec)
+            .id(id)
+            .command(command)
+            .sequence(sequence)
+            .payload(&payload)
+            .to_wire()
+            .unwrap();
+        let (head, tail) = wire.split_at(split.index(wire.len() + 1));
+
+        let mut decoder = decoder(&spec, false);
+        let mut results: Vec<_> = decoder.feed(head).collect();
+        results.extend(decoder.feed(tail));
+
+        prop_assert_eq!(results.len(), 1, "{:?}", results);
+        prop_assert_eq!(results[0].as_ref().unwrap().payload(), &payload[..]);
+    }
+
+    #[test]
+    fn random_stream_never_panics(
+        spec in spec_strategy(),
+        resync in any::<bool>(),
+        chunks in prop::collection::vec(prop::collection::vec(any::<u8>(), 0..64), 0..16),
+    ) {
+        let mut decoder = decoder(&spec, resync);
+        for chunk in &chunks {
+            for packet in decoder.feed(chunk).flatten() {
+                let _ = packet.to_string();
+                let _ = packet.serialize();
+            }
+        }
+    }
+
+    #[test]
+    fn parse_never_panics(spec in spec_strategy(), bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let mut packet = PACKET::with_spec(spec);
+        for byte in bytes {
+            if let ParseEvent::Frame(frame) = packet.parse(byte) {
+                let _ = frame.to_string();
+            }
+        }
+    }
+
+    // 손으로 채운 헤더는 LEN 과 DATA 길이가 맞지 않을 수 있다
+    #[test]
+    fn inconsistent_header_never_panics(
+        spec in spec_strategy(),
+        length in any::<u16>(),
+        data in prop::collection::vec(any::<u8>(), 0..32),
+    ) {
+        let mut packet = PACKET::with_spec(spec);
+        packet.header.length = length;
+        packet.data = data;
+
+        let _ = packet.payload_len();
+        let _ = packet.to_string();
+        let _ = packet.serialize();
+        let _ = packet.to_wire();
+    }
+
+    // CS 가 깨진 프레임도 로그에 보여줄 수 있도록 필드를 다시 나눌 수 있어야 한다
+    #[test]
+    fn checksum_mismatch_raw_splits_back_into_fields(
+        (spec, id, command, sequence, payload) in frame_strategy(),
+    ) {
+        let packet = PacketBuilder::new()
+            .spec(&spec)
+            .id(id)
+            .command(command)
+            .sequence(sequence)
+            .payload(&payload)
+            .build()
+            .unwrap();
+        let mut broken = packet.clone();
+        broken.checksum ^= 0x01;
+
+        let mut decoder = decoder(&spec, false);
+        let results: Vec<_> = decoder.feed(&broken.to_wire()).collect();
+        let Some(Err(ParseError::ChecksumMismatch { raw, .. })) = results.first() else {
+            panic!("expected checksum mismatch, got {:?}", results);
+        };
+
+        let split = PACKET::from_raw(&spec, raw).unwrap();
+        prop_assert_eq!(split.header.id, id);
+        prop_assert_eq!(split.header.command, command);
+        prop_assert_eq!(split.header.length, packet.header.length);
+        prop_assert_eq!(split.payload(), &payload[..]);
+        prop_assert_eq!(split.checksum, broken.checksum);
+    }
+}
+
+// validate 를 거치지 않은 spec (sync 없음) 으로도 panic 하지 않고 바이트를 버린다
+#[test]
+fn empty_sync_discards_instead_of_panicking() {
+    let spec = FrameSpec {
+        sync: Vec::new(),
+        ..FrameSpec::default()
+    };
+    assert!(spec.validate().is_err());
+
+    let mut packet = PACKET::with_spec(spec);
+    for byte in [0x02, 0xC1, 0x08] {
+        assert!(matches!(
+            packet.parse(byte),
+            ParseEvent::Error(ParseError::DiscardedBytes(1))
+        ));
+    }
+}
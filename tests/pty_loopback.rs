@@ -0,0 +1,168 @@
+//! pty 한 쌍으로 장치 없이 수신/디코딩 경로를 시험한다.
+//! master 쪽에 프레임을 쓰고, slave 쪽 SERIAL 에서 읽어 Decoder 에 넣는다.
+#![cfg(unix)]
+#![allow(non_snake_case)]
+
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use serialport::TTYPort;
+use RUST_tutorial::protocol::{Decoder, DecoderConfig, PacketBuilder, ParseError, PACKET};
+use RUST_tutorial::serial::SERIAL;
+use RUST_tutorial::session::Session;
+use RUST_tutorial::transport::Transport;
+
+// main.rs 에 남아 있던 packet_temp_dummy
+const DUMMY: [u8; 8] = [0x02, 0xC1, 0x08, 0x12, 0x00, 0x04, 0x78, 0x9F];
+const READ_DEADLINE: Duration = Duration::from_secs(2);
+
+fn pty_pair() -> (TTYPort, SERIAL) {
+    let (master, slave) = TTYPort::pair().expect("cannot open pty pair");
+    let serial = SERIAL::from_port(Box::new(slave)).expect("cannot use pty slave");
+    (master, serial)
+}
+
+// `count` 개의 결과가 나오거나 READ_DEADLINE 이 지날 때까지 읽는다
+fn read_results(
+    serial: &mut SERIAL,
+    decoder: &mut Decoder,
+    count: usize,
+) -> Vec<Result<PACKET, ParseError>> {
+    let deadline = Instant::now() + READ_DEADLINE;
+    let mut results = Vec::new();
+
+    while results.len() < count && Instant::now() < deadline {
+        let bytes = Transport::read(serial).expect("pty read failed");
+        results.extend(decoder.feed(&bytes));
+    }
+
+    results
+}
+
+fn decoder() -> Decoder {
+    let mut decoder = Decoder::new();
+    decoder.set_resync(true);
+    decoder
+}
+
+#[test]
+fn dummy_frame_is_decoded() {
+    let (mut master, mut serial) = pty_pair();
+    let mut decoder = decoder();
+
+    master.write_all(&DUMMY).unwrap();
+    let results = read_results(&mut serial, &mut decoder, 1);
+
+    assert_eq!(results.len(), 1);
+    let packet = results[0].as_ref().expect("frame should decode");
+    assert_eq!(packet.header.id, 0xC1);
+    assert_eq!(packet.header.length, 0x08);
+    assert_eq!(packet.header.command, 0x12);
+    assert_eq!(packet.header.sequence, 0x00);
+    assert_eq!(packet.payload(), [0x04, 0x78]);
+    assert_eq!(packet.checksum, 0x9F);
+    assert_eq!(packet.serialize(), DUMMY);
+}
+
+#[test]
+fn checksum_failure_is_reported_and_next_frame_survives() {
+    let (mut master, mut serial) = pty_pair();
+    let mut decoder = decoder();
+
+    let mut corrupted = DUMMY;
+    corrupted[7] ^= 0xFF;
+    master.write_all(&corrupted).unwrap();
+    master.write_all(&DUMMY).unwrap();
+    // CS 오류, resync 로 건너뛴 바이트, 정상 프레임
+    let results = read_results(&mut serial, &mut decoder, 3);
+
+    assert_eq!(results.len(), 3, "{:?}", results);
+    match &results[0] {
+        Err(ParseError::ChecksumMismatch { raw, .. }) => assert_eq!(raw, &corrupted),
+        other => panic!("expected checksum mismatch, got {:?}", other),
+    }
+    assert!(matches!(results[1], Err(ParseError::DiscardedBytes(_))));
+    assert_eq!(results[2].as_ref().unwrap().payload(), [0x04, 0x78]);
+    assert_eq!(decoder.stats().checksum_errors, 1);
+    assert_eq!(decoder.stats().frames, 1);
+}
+
+#[test]
+fn frame_split_across_reads_is_reassembled() {
+    let (mut master, mut serial) = pty_pair();
+    let mut decoder = decoder();
+
+    // 바이트마다 따로 읽히도록 사이에 읽기를 끼워 넣는다
+    let mut results = Vec::new();
+    for byte in DUMMY {
+        master.write_all(&[byte]).unwrap();
+        master.flush().unwrap();
+        thread::sleep(Duration::from_millis(5));
+        let bytes = Transport::read(&mut serial).unwrap();
+        results.extend(decoder.feed(&bytes));
+    }
+    if results.is_empty() {
+        results.extend(read_results(&mut serial, &mut decoder, 1));
+    }
+
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0].as_ref().unwrap().serialize(), DUMMY);
+}
+
+#[test]
+fn back_to_back_frames_in_one_write() {
+    let (mut master, mut serial) = pty_pair();
+    let mut decoder = decoder();
+
+    let mut stream = Vec::new();
+    for sequence in 0..10u8 {
+        let payload = [sequence; 3];
+        let frame = PacketBuilder::new()
+            .id(0xC1)
+            .command(0x20)
+            .sequence(sequence)
+            .payload(&payload)
+            .to_bytes()
+            .unwrap();
+        stream.extend(frame);
+    }
+    // 프레임 사이의 잡음은 resync 로 건너뛴다
+    stream.splice(0..0, [0xAA, 0x55]);
+    master.write_all(&stream).unwrap();
+
+    let packets: Vec<PACKET> = read_results(&mut serial, &mut decoder, 11)
+        .into_iter()
+        .filter_map(Result::ok)
+        .collect();
+
+    assert_eq!(packets.len(), 10);
+    for (sequence, packet) in packets.iter().enumerate() {
+        assert_eq!(packet.header.sequence, sequence as u8);
+        assert_eq!(packet.payload(), [sequence as u8; 3]);
+    }
+}
+
+#[test]
+fn session_reports_skipped_bytes() {
+    let (mut master, serial) = pty_pair();
+    let config = DecoderConfig {
+        resync: true,
+        ..Default::default()
+    };
+    let session = Session::open(Box::new(serial), Arc::new(Mutex::new(config)), false);
+
+    master.write_all(&[0xAA, 0x55, 0x00]).unwrap();
+    master.write_all(&DUMMY).unwrap();
+
+    let deadline = Instant::now() + READ_DEADLINE;
+    while session.decoder_stats().frames == 0 && Instant::now() < deadline {
+        thread::sleep(Duration::from_millis(10));
+    }
+    let stats = session.decoder_stats();
+    assert_eq!(stats.frames, 1);
+    assert_eq!(stats.skipped_bytes, 3);
+}
@@ -0,0 +1,122 @@
+//! RFC 2217 클라이언트를 로컬 TcpListener (ser2net 대신) 에 붙여서 협상과 IAC 처리를 시험한다.
+#![allow(non_snake_case)]
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+use RUST_tutorial::rfc2217::Rfc2217Transport;
+use RUST_tutorial::serial::{DataBits, FlowControl, Parity, SerialConfig, StopBits};
+use RUST_tutorial::transport::Transport;
+
+const IAC: u8 = 255;
+const DONT: u8 = 254;
+const DO: u8 = 253;
+const WONT: u8 = 252;
+const WILL: u8 = 251;
+const SB: u8 = 250;
+const SE: u8 = 240;
+const COM_PORT: u8 = 44;
+
+const DEADLINE: Duration = Duration::from_secs(5);
+
+fn connect(config: &SerialConfig) -> (Rfc2217Transport, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    let transport = Rfc2217Transport::connect(&address, config).unwrap();
+    let (server, _) = listener.accept().unwrap();
+    server.set_read_timeout(Some(DEADLINE)).unwrap();
+
+    (transport, server)
+}
+
+fn read_n(server: &mut TcpStream, n: usize) -> Vec<u8> {
+    let mut bytes = vec![0; n];
+    server.read_exact(&mut bytes).unwrap();
+    bytes
+}
+
+// IAC SB COM-PORT-OPTION <command> <value> IAC SE
+fn com_port(command: u8, value: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![IAC, SB, COM_PORT, command];
+    bytes.extend(value);
+    bytes.extend([IAC, SE]);
+    bytes
+}
+
+// 접속하자마자 client 가 보내야 하는 옵션 요청과 회선 설정
+fn negotiation(baud_rate: u32, data_size: u8, parity: u8, stop_size: u8, control: u8) -> Vec<u8> {
+    let mut bytes = vec![
+        IAC, WILL, 0, // BINARY
+        IAC, DO, 0, //
+        IAC, DO, 3, // SUPPRESS-GO-AHEAD
+        IAC, WILL, COM_PORT,
+    ];
+    bytes.extend(com_port(1, &baud_rate.to_be_bytes())); // SET-BAUDRATE
+    bytes.extend(com_port(2, &[data_size])); // SET-DATASIZE
+    bytes.extend(com_port(3, &[parity])); // SET-PARITY
+    bytes.extend(com_port(4, &[stop_size])); // SET-STOPSIZE
+    bytes.extend(com_port(5, &[control])); // SET-CONTROL
+    bytes
+}
+
+// 9600 8N1, 흐름제어 없음
+fn default_negotiation() -> Vec<u8> {
+    negotiation(9600, 8, 1, 1, 1)
+}
+
+#[test]
+fn negotiates_line_settings_on_connect() {
+    let config = SerialConfig {
+        baud_rate: 115_200,
+        data_bits: DataBits::Seven,
+        parity: Parity::Even,
+        stop_bits: StopBits::Two,
+        flow_control: FlowControl::Hardware,
+    };
+    let (_transport, mut server) = connect(&config);
+    let expected = negotiation(115_200, 7, 3, 2, 3);
+    assert_eq!(read_n(&mut server, expected.len()), expected);
+
+    let (_transport, mut server) = connect(&SerialConfig::default());
+    let expected = default_negotiation();
+    assert_eq!(read_n(&mut server, expected.len()), expected);
+}
+
+#[test]
+fn doubles_iac_in_outgoing_data() {
+    let (mut transport, mut server) = connect(&SerialConfig::default());
+    read_n(&mut server, default_negotiation().len());
+
+    transport.write(&[0x02, 0xFF, 0x03]).unwrap();
+    assert_eq!(read_n(&mut server, 4), [0x02, IAC, IAC, 0x03]);
+
+    // 수신 쓰레드와 따로 쓰는 송신 핸들도 같은 규칙을 따른다
+    let mut writer = transport.writer().unwrap();
+    writer.write(&[0xFF, 0x10]).unwrap();
+    assert_eq!(read_n(&mut server, 3), [IAC, IAC, 0x10]);
+}
+
+#[test]
+fn strips_commands_and_refuses_unknown_options() {
+    let (mut transport, mut server) = connect(&SerialConfig::default());
+    read_n(&mut server, default_negotiation().len());
+
+    let mut incoming = vec![IAC, IAC, 0x41];
+    incoming.extend([IAC, DO, 24]); // TERMINAL-TYPE
+    incoming.extend([IAC, WILL, 31]); // NAWS
+    incoming.extend(com_port(101, &115_200u32.to_be_bytes())); // server 의 SET-BAUDRATE 응답
+    incoming.extend([IAC, WILL, COM_PORT]); // 우리가 요청한 옵션은 다시 답하지 않는다
+    incoming.push(0x42);
+    server.write_all(&incoming).unwrap();
+
+    let deadline = Instant::now() + DEADLINE;
+    let mut data = Vec::new();
+    while data.len() < 3 && Instant::now() < deadline {
+        data.extend(transport.read().unwrap());
+    }
+    assert_eq!(data, [IAC, 0x41, 0x42]);
+    assert_eq!(read_n(&mut server, 6), [IAC, WONT, 24, IAC, DONT, 31]);
+}
@@ -0,0 +1,102 @@
+//! 반복 전송을 Loopback 세션으로 시험한다. 보낸 프레임이 그대로 돌아와서 RX 로도 보인다.
+#![allow(non_snake_case)]
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use RUST_tutorial::packet_log::{ChecksumStatus, Direction, LogEntry};
+use RUST_tutorial::protocol::{DecoderConfig, PacketBuilder};
+use RUST_tutorial::scheduler::Schedule;
+use RUST_tutorial::session::{Session, SessionEvent};
+use RUST_tutorial::transport::Loopback;
+
+const DEADLINE: Duration = Duration::from_secs(5);
+
+fn loopback_session() -> Session {
+    Session::open(
+        Box::new(Loopback::new()),
+        Arc::new(Mutex::new(DecoderConfig::default())),
+        false,
+    )
+}
+
+fn schedule(count: Option<u32>, interval: Duration) -> Schedule {
+    Schedule {
+        packet: PacketBuilder::new()
+            .id(0xC1)
+            .command(0x20)
+            .sequence(0xFE)
+            .payload(&[0x01, 0x02])
+            .build()
+            .unwrap(),
+        interval,
+        count,
+        increment_sequence: true,
+        recompute_checksum: true,
+    }
+}
+
+// SendDone 이 오고 RX 가 `rx_count` 개 모일 때까지 모은다
+fn collect(session: &mut Session, rx_count: usize) -> (Vec<LogEntry>, Vec<LogEntry>, Option<u32>) {
+    let deadline = Instant::now() + DEADLINE;
+    let (mut tx, mut rx, mut done) = (Vec::new(), Vec::new(), None);
+
+    while Instant::now() < deadline && (done.is_none() || rx.len() < rx_count) {
+        for event in session.poll_events() {
+            match event {
+                SessionEvent::Packet(entry) if entry.direction == Direction::Tx => tx.push(entry),
+                SessionEvent::Packet(entry) => rx.push(entry),
+                SessionEvent::SendDone(sent) => done = Some(sent),
+                other => panic!("unexpected event {:?}", other),
+            }
+        }
+        thread::sleep(Duration::from_millis(5));
+    }
+
+    (tx, rx, done)
+}
+
+#[test]
+fn sends_count_times_with_incrementing_sequence() {
+    let mut session = loopback_session();
+    let started = Instant::now();
+    session.start_schedule(schedule(Some(5), Duration::from_millis(20)));
+
+    let (tx, rx, done) = collect(&mut session, 5);
+
+    assert_eq!(done, Some(5));
+    // 첫 프레임은 바로 보내므로 4 주기
+    assert!(started.elapsed() >= Duration::from_millis(80));
+    // 송신이 수신 쓰레드의 read (READ_TIMEOUT) 를 기다리지 않으므로 주기가 밀리지 않는다
+    let span = tx.last().unwrap().time - tx.first().unwrap().time;
+    assert!(span >= chrono::Duration::milliseconds(79), "{:?}", span);
+    assert!(span < chrono::Duration::milliseconds(120), "{:?}", span);
+    let sequences: Vec<Option<u8>> = tx.iter().map(|entry| entry.sequence).collect();
+    assert_eq!(
+        sequences,
+        [Some(0xFE), Some(0xFF), Some(0x00), Some(0x01), Some(0x02)]
+    );
+    // SEQ 를 바꿀 때마다 CS 를 다시 계산하므로 돌아온 프레임도 모두 정상
+    assert!(tx.iter().all(|entry| entry.status == ChecksumStatus::Ok));
+    assert_eq!(rx.len(), 5);
+    assert!(session.schedule_progress().is_none());
+}
+
+#[test]
+fn stop_ends_an_unbounded_schedule() {
+    let mut session = loopback_session();
+    session.start_schedule(schedule(None, Duration::from_millis(10)));
+    thread::sleep(Duration::from_millis(50));
+
+    let (sent, count) = session.schedule_progress().unwrap();
+    assert!(sent > 0);
+    assert_eq!(count, None);
+
+    session.stop_schedule();
+    assert!(session.schedule_progress().is_none());
+    let (tx, _, done) = collect(&mut session, 0);
+    assert_eq!(done, Some(tx.len() as u32));
+}
@@ -0,0 +1,102 @@
+//! 세션을 TCP 로 공유했을 때 client 역할을 시험한다. 세션은 Loopback 한쪽 끝에 열고 다른 끝을 장치로 쓴다.
+#![allow(non_snake_case)]
+
+use std::{
+    io::{Read, Write},
+    net::TcpStream,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use RUST_tutorial::packet_log::Direction;
+use RUST_tutorial::protocol::{DecoderConfig, PacketBuilder};
+use RUST_tutorial::session::{Session, SessionEvent};
+use RUST_tutorial::transport::{Loopback, Transport};
+
+const DEADLINE: Duration = Duration::from_secs(5);
+
+fn wait_for(mut done: impl FnMut() -> bool) {
+    let deadline = Instant::now() + DEADLINE;
+    while !done() {
+        assert!(Instant::now() < deadline, "timed out");
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+// client 는 접속한 순서대로 hub 에 들어가야 하므로 하나씩 기다린다
+fn connect_client(session: &Session, clients: usize) -> TcpStream {
+    let (address, _) = session.share_status().unwrap();
+    let stream = TcpStream::connect(address).unwrap();
+    stream.set_read_timeout(Some(DEADLINE)).unwrap();
+    wait_for(|| session.share_status().unwrap().1 == clients);
+    stream
+}
+
+fn read_n(stream: &mut TcpStream, n: usize) -> Vec<u8> {
+    let mut bytes = vec![0; n];
+    stream.read_exact(&mut bytes).unwrap();
+    bytes
+}
+
+// 장치 쪽에서 `n` 바이트를 받거나 `wait` 가 지날 때까지 받은 바이트
+fn device_read(device: &mut Loopback, n: usize, wait: Duration) -> Vec<u8> {
+    let deadline = Instant::now() + wait;
+    let mut bytes = Vec::new();
+    while bytes.len() < n && Instant::now() < deadline {
+        bytes.extend(device.read().unwrap());
+    }
+    bytes
+}
+
+#[test]
+fn first_client_writes_and_everyone_reads() {
+    let (mut device, host) = Loopback::pair();
+    let mut session = Session::open(
+        Box::new(host),
+        Arc::new(Mutex::new(DecoderConfig::default())),
+        false,
+    );
+    session.start_share("127.0.0.1:0").unwrap();
+
+    let mut writer = connect_client(&session, 1);
+    let mut reader = connect_client(&session, 2);
+
+    // 장치가 보낸 바이트는 모든 client 에 간다
+    device.write(&[0x10, 0x20, 0x30]).unwrap();
+    assert_eq!(read_n(&mut writer, 3), [0x10, 0x20, 0x30]);
+    assert_eq!(read_n(&mut reader, 3), [0x10, 0x20, 0x30]);
+
+    // 먼저 접속한 client 만 장치로 보낼 수 있고, 그 프레임은 로그에 TX 로 남는다
+    let frame = PacketBuilder::new()
+        .id(0xC1)
+        .command(0x12)
+        .payload(&[0x04])
+        .to_bytes()
+        .unwrap();
+    writer.write_all(&frame).unwrap();
+    assert_eq!(device_read(&mut device, frame.len(), DEADLINE), frame);
+    let mut tx = Vec::new();
+    wait_for(|| {
+        tx.extend(
+            session
+                .poll_events()
+                .into_iter()
+                .filter_map(|event| match event {
+                    SessionEvent::Packet(entry) if entry.direction == Direction::Tx => Some(entry),
+                    _ => None,
+                }),
+        );
+        !tx.is_empty()
+    });
+    assert_eq!((tx[0].id, tx[0].command), (0xC1, 0x12));
+
+    reader.write_all(&[0xAA]).unwrap();
+    assert!(device_read(&mut device, 1, Duration::from_millis(300)).is_empty());
+
+    // writer 가 나가면 다음 client 가 이어받는다
+    drop(writer);
+    wait_for(|| session.share_status().unwrap().1 == 1);
+    reader.write_all(&[0xBB]).unwrap();
+    assert_eq!(device_read(&mut device, 1, DEADLINE), [0xBB]);
+}
@@ -0,0 +1,105 @@
+//! 여러 쓰레드가 함께 쓰는 SharedTransport 를 시험한다.
+#![allow(non_snake_case)]
+
+use std::{
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use RUST_tutorial::serial::SerialError;
+use RUST_tutorial::transport::{SharedTransport, Transport};
+
+const DEADLINE: Duration = Duration::from_secs(5);
+
+// reopen 이 `gate` 를 받을 때까지 끝나지 않는 연결. 송신 핸들이 없어서 write 는 transport 로 온다
+struct SlowReopen {
+    open: bool,
+    reopening: mpsc::Sender<()>,
+    gate: mpsc::Receiver<()>,
+    written: Arc<Mutex<Vec<u8>>>,
+}
+
+impl Transport for SlowReopen {
+    fn name(&self) -> String {
+        String::from("slow reopen")
+    }
+
+    fn read(&mut self) -> Result<Vec<u8>, SerialError> {
+        Ok(Vec::new())
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<(), SerialError> {
+        if !self.open {
+            return Err(SerialError::NotOpen);
+        }
+        self.written.lock().unwrap().extend(data);
+        Ok(())
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+    }
+
+    fn is_open(&self) -> bool {
+        self.open
+    }
+
+    fn can_reopen(&self) -> bool {
+        true
+    }
+
+    fn reopen(&mut self) -> Result<(), SerialError> {
+        self.reopening.send(()).unwrap();
+        self.gate.recv().unwrap();
+        self.open = true;
+        Ok(())
+    }
+}
+
+#[test]
+fn write_during_reopen_does_not_deadlock() {
+    let (reopening_tx, reopening) = mpsc::channel();
+    let (gate, gate_rx) = mpsc::channel();
+    let written = Arc::new(Mutex::new(Vec::new()));
+    let shared = Arc::new(SharedTransport::new(Box::new(SlowReopen {
+        open: true,
+        reopening: reopening_tx,
+        gate: gate_rx,
+        written: Arc::clone(&written),
+    })));
+    // 끊긴 상태. 송신 핸들이 없다
+    shared.close();
+
+    let (done_tx, done) = mpsc::channel();
+    let reopen = {
+        let shared = Arc::clone(&shared);
+        let done_tx = done_tx.clone();
+        thread::spawn(move || {
+            shared.reopen().unwrap();
+            done_tx.send("reopen").unwrap();
+        })
+    };
+    reopening.recv_timeout(DEADLINE).unwrap();
+
+    // reopen 이 transport 를 잡고 있는 동안 송신한다
+    let write = {
+        let shared = Arc::clone(&shared);
+        thread::spawn(move || {
+            shared.write(&[0x02, 0xC1]).unwrap();
+            done_tx.send("write").unwrap();
+        })
+    };
+    thread::sleep(Duration::from_millis(50));
+    gate.send(()).unwrap();
+
+    let mut finished = vec![
+        done.recv_timeout(DEADLINE).expect("deadlock"),
+        done.recv_timeout(DEADLINE).expect("deadlock"),
+    ];
+    finished.sort();
+    assert_eq!(finished, ["reopen", "write"]);
+    reopen.join().unwrap();
+    write.join().unwrap();
+    assert_eq!(*written.lock().unwrap(), [0x02, 0xC1]);
+}